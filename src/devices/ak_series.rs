@@ -4,40 +4,78 @@
 //! - AK500S DIGITAL
 //! - AK620 DIGITAL
 
-use crate::{devices::AUTO_MODE_INTERVAL, monitor::cpu::Cpu};
-use super::{device_error, Mode};
+use crate::{monitor::{external, hwmon, sampler::{Metric, Sample, Sampler}}, utils::config::Config};
+use super::{alarm::Alarm, device_error, status_bar::StatusBar, Mode, ModeSelector};
 use hidapi::HidApi;
-use std::{thread::sleep, time::{Duration, Instant}};
+use std::time::Duration;
 
 pub const DEFAULT_MODE: Mode = Mode::CpuTemperature;
+pub const AUTO_MODES: [Mode; 2] = [Mode::CpuTemperature, Mode::CpuUsage];
 pub const TEMP_LIMIT_C: u8 = 90;
 pub const TEMP_LIMIT_F: u8 = 194;
 
 pub struct Display {
-    cpu: Cpu,
+    sampler: Sampler,
     pub mode: Mode,
+    selector: ModeSelector,
     update: Duration,
-    fahrenheit: bool,
-    alarm: bool,
+    pub alarm: Alarm,
+    status_bar: StatusBar,
 }
 
 impl Display {
-    pub fn new(cpu: Cpu, mode: &Mode, update: Duration, fahrenheit: bool, alarm: bool) -> Self {
+    pub fn new(mut sampler: Sampler, mode: &Mode, update: Duration, alarm: bool, config: &Config) -> Self {
         // Verify the display mode
+        let supported = |mode: &Mode| match mode {
+            Mode::Hwmon(symbol) => hwmon::unit(symbol) == hwmon::Unit::Temperature,
+            Mode::External => matches!(external::unit(), external::Unit::Percent | external::Unit::Celsius),
+            _ => matches!(
+                mode,
+                Mode::Auto
+                    | Mode::CpuTemperature
+                    | Mode::CpuUsage
+                    | Mode::CpuMaxCoreUsage
+                    | Mode::CpuPerformanceUsage
+                    | Mode::CpuEfficiencyUsage
+                    | Mode::CpuActiveUsage
+                    | Mode::CgroupUsage
+                    | Mode::RamUsage
+                    | Mode::SwapUsage
+                    | Mode::DiskTemperature
+                    | Mode::BoardTemperature
+                    | Mode::GpuTemperature
+                    | Mode::GpuUsage
+            ),
+        };
         let mode = match mode {
             Mode::Default => DEFAULT_MODE,
-            Mode::Auto => Mode::Auto,
-            Mode::CpuTemperature => Mode::CpuTemperature,
-            Mode::CpuUsage => Mode::CpuUsage,
+            _ if supported(mode) => mode.clone(),
             _ => mode.support_error(),
         };
 
+        // Verify the display modes of the rules & profiles
+        let selector = ModeSelector::new(&mode, &AUTO_MODES, config);
+        selector.verify(supported, |_| false);
+        selector.metrics().into_iter().for_each(|metric| sampler.require(metric));
+
+        // Take the initial readings (e.g. energy consumption) only for the displayed metrics
+        selector.mode_metrics().into_iter().for_each(|metric| sampler.require(metric));
+
+        // Read the alarm settings
+        let alarm = Alarm::read(config, alarm, if sampler.fahrenheit() { TEMP_LIMIT_F } else { TEMP_LIMIT_C }, &[]);
+        alarm.require(&mut sampler);
+
+        // Read the status bar settings
+        let status_bar = StatusBar::read(config, "status_bar", Metric::CpuUsage, sampler.fahrenheit());
+        sampler.require(status_bar.metric.clone());
+
         Display {
-            cpu,
+            sampler,
             mode,
+            selector,
             update,
-            fahrenheit,
             alarm,
+            status_bar,
         }
     }
 
@@ -46,7 +84,22 @@ impl Display {
         let device = api.open(vid, pid).unwrap_or_else(|_| device_error());
 
         // Display warning if a required module is missing
-        self.cpu.warn_temp();
+        let modes = self.selector.modes();
+        self.sampler.cpu.warn_temp();
+        if
+            modes.iter().any(|mode| matches!(mode, Mode::GpuTemperature | Mode::GpuUsage)) ||
+            self.alarm.uses(Metric::GpuTemperature) ||
+            self.status_bar.is_gpu()
+        {
+            self.sampler.gpu.warn_missing();
+        }
+        if
+            self.alarm.uses(Metric::CpuPower) ||
+            self.status_bar.metric == Metric::CpuPower
+        {
+            self.sampler.cpu.warn_rapl();
+        }
+        self.selector.mode_metrics().into_iter().for_each(|metric| self.sampler.warn_missing_sensor(metric));
 
         // Data packet
         let mut data: [u8; 64] = [0; 64];
@@ -60,72 +113,33 @@ impl Display {
         }
 
         // Display loop
-        match self.mode {
-            Mode::Auto => {
-                let mut initial_update = self.update;
-                let mut mode = Mode::CpuTemperature;
-                loop {
-                    // Initial update
-                    device.write(&self.status_message(&data, &mode, initial_update)).unwrap();
-
-                    // Update until timeout
-                    let timeout = Instant::now() + AUTO_MODE_INTERVAL;
-                    while Instant::now() + self.update < timeout {
-                        device.write(&self.status_message(&data, &mode, self.update)).unwrap();
-                    }
-
-                    // Make the next initial update faster to fit the timeframe
-                    initial_update = timeout - Instant::now();
-
-                    // Switch to the next display mode
-                    mode = match mode {
-                        Mode::CpuTemperature => Mode::CpuUsage,
-                        Mode::CpuUsage => Mode::CpuTemperature,
-                        _ => DEFAULT_MODE,
-                    }
-                }
-            }
-            _ => loop {
-                device.write(&self.status_message(&data, &self.mode, self.update)).unwrap();
-            }
+        let mut selector = self.selector.clone();
+        let mut alarm = self.alarm.clone();
+        loop {
+            let sample = self.sampler.sample(selector.update(self.update));
+            let mode = selector.select(&sample);
+            let alarm = alarm.update(&sample);
+            device.write(&self.status_message(&data, &mode, alarm, &sample)).unwrap();
         }
     }
 
-    /// Reads the CPU status information and returns the data packet.
-    fn status_message(&self, inital_data: &[u8; 64], mode: &Mode, update: Duration) -> [u8; 64] {
+    /// Reads the system status information and returns the data packet.
+    fn status_message(&self, inital_data: &[u8; 64], mode: &Mode, alarm: bool, sample: &Sample) -> [u8; 64] {
         // Clone the data packet
         let mut data = inital_data.clone();
 
-        // Read CPU utilization
-        let cpu_instant = self.cpu.read_instant();
-
-        // Wait
-        sleep(update);
-
-        // Calculate usage & temperature
-        let usage = self.cpu.get_usage(cpu_instant);
-        let temp = self.cpu.get_temp(self.fahrenheit);
-
-        // Main display
-        match mode {
-            Mode::CpuTemperature => {
-                data[1] = if self.fahrenheit { 35 } else { 19 };
-                data[3] = temp / 100;
-                data[4] = temp % 100 / 10;
-                data[5] = temp % 10;
-            }
-            Mode::CpuUsage => {
-                data[1] = 76;
-                data[3] = usage / 100;
-                data[4] = usage % 100 / 10;
-                data[5] = usage % 10;
-            }
-            _ => (),
+        // Main display (values above 999 are displayed as 999)
+        if let Some(metric) = mode.metric() {
+            let value = (sample.get(metric).round() as u16).min(999);
+            data[1] = if !mode.is_temperature() { 76 } else if self.sampler.fahrenheit() { 35 } else { 19 };
+            data[3] = (value / 100) as u8;
+            data[4] = (value % 100 / 10) as u8;
+            data[5] = (value % 10) as u8;
         }
         // Status bar
-        data[2] = if usage < 15 { 1 } else { (usage as f32 / 10.0).round() as u8 };
+        data[2] = self.status_bar.segments(sample);
         // Alarm
-        data[6] = (self.alarm && temp >= if self.fahrenheit { TEMP_LIMIT_F } else { TEMP_LIMIT_C }) as u8;
+        data[6] = alarm as u8;
 
         data
     }
@@ -0,0 +1,256 @@
+//! Triggers the alarm of the display when the selected metric reaches the limit.
+//!
+//! The default settings of the device can be changed in the configuration file:
+//! ```ini
+//! [alarm]
+//! source = max_temp
+//! limit = 85
+//! hysteresis = 5
+//! effect = blink
+//! ```
+//! Devices without a hardware alarm emulate it with the selected effect.
+
+use crate::{error, monitor::sampler::{Metric, Sample, Sampler}, utils::config::Config};
+use std::process::exit;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum AlarmSource {
+    CpuTemperature,
+    GpuTemperature,
+    MaxTemperature,
+    CpuPower,
+}
+
+impl AlarmSource {
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            AlarmSource::CpuTemperature => "cpu_temp",
+            AlarmSource::GpuTemperature => "gpu_temp",
+            AlarmSource::MaxTemperature => "max_temp",
+            AlarmSource::CpuPower => "cpu_power",
+        }
+    }
+
+    pub fn get(symbol: &str) -> Option<AlarmSource> {
+        match symbol {
+            "cpu_temp" => Some(Self::CpuTemperature),
+            "gpu_temp" => Some(Self::GpuTemperature),
+            "max_temp" => Some(Self::MaxTemperature),
+            "cpu_power" => Some(Self::CpuPower),
+            _ => None,
+        }
+    }
+
+    /// Returns the metrics that are compared to the limit.
+    fn metrics(&self) -> &'static [Metric] {
+        match self {
+            AlarmSource::CpuTemperature => &[Metric::CpuTemperature],
+            AlarmSource::GpuTemperature => &[Metric::GpuTemperature],
+            AlarmSource::MaxTemperature => &[Metric::CpuTemperature, Metric::GpuTemperature],
+            AlarmSource::CpuPower => &[Metric::CpuPower],
+        }
+    }
+}
+
+/// Effects of the software alarm.
+#[derive(PartialEq, Clone, Copy)]
+pub enum AlarmEffect {
+    /// Blinks the displayed values.
+    Blink,
+    /// Blinks the whole display.
+    BlinkAll,
+    /// Inverts the pixels of the display.
+    Invert,
+    /// Forces the temperature view of the alarm source.
+    ShowTemp,
+}
+
+impl AlarmEffect {
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            AlarmEffect::Blink => "blink",
+            AlarmEffect::BlinkAll => "blink_all",
+            AlarmEffect::Invert => "invert",
+            AlarmEffect::ShowTemp => "show_temp",
+        }
+    }
+
+    pub fn get(symbol: &str) -> Option<AlarmEffect> {
+        match symbol {
+            "blink" => Some(Self::Blink),
+            "blink_all" => Some(Self::BlinkAll),
+            "invert" => Some(Self::Invert),
+            "show_temp" => Some(Self::ShowTemp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Alarm {
+    pub enabled: bool,
+    pub source: AlarmSource,
+    pub limit: u16,
+    pub hysteresis: u16,
+    pub effect: Option<AlarmEffect>,
+    active: bool,
+    blink: bool,
+}
+
+impl Alarm {
+    /// Reads the `[alarm]` section of the configuration, the temperature limit of the device is used by default.
+    ///
+    /// Devices without a hardware alarm specify the supported effects, the first one is the default.
+    pub fn read(config: &Config, enabled: bool, temp_limit: u8, effects: &[AlarmEffect]) -> Self {
+        let (source, limit, hysteresis, effect) = match config.sections("alarm").last() {
+            Some(section) => {
+                let source = match section.get("source") {
+                    Some(symbol) => AlarmSource::get(symbol).unwrap_or_else(|| section.value_error("source", symbol)),
+                    None => AlarmSource::CpuTemperature,
+                };
+                let limit = match section.parse::<u16>("limit") {
+                    Some(limit) => limit,
+                    None if source == AlarmSource::CpuPower => section.missing_error("limit"),
+                    None => temp_limit.into(),
+                };
+                let effect = match section.get("effect") {
+                    Some(symbol) => match AlarmEffect::get(symbol) {
+                        Some(effect) if effects.contains(&effect) => Some(effect),
+                        Some(_) => {
+                            error!(format!("Alarm effect \"{symbol}\" is not supported on your device"));
+                            exit(1);
+                        }
+                        None => section.value_error("effect", symbol),
+                    },
+                    None => effects.first().copied(),
+                };
+                (source, limit, section.parse::<u16>("hysteresis").unwrap_or(0), effect)
+            }
+            None => (AlarmSource::CpuTemperature, temp_limit.into(), 0, effects.first().copied()),
+        };
+
+        Alarm {
+            enabled,
+            source,
+            limit,
+            hysteresis,
+            effect,
+            active: false,
+            blink: false,
+        }
+    }
+
+    /// Makes sure that the sampler can provide the metrics of the alarm.
+    pub fn require(&self, sampler: &mut Sampler) {
+        if self.enabled {
+            self.source.metrics().iter().for_each(|metric| sampler.require(metric.clone()));
+        }
+    }
+
+    /// Checks if the metric is read by the alarm.
+    pub fn uses(&self, metric: Metric) -> bool {
+        self.enabled && self.source.metrics().contains(&metric)
+    }
+
+    /// Checks the alarm state. An active alarm stays on until the value drops below the hysteresis band.
+    pub fn update(&mut self, sample: &Sample) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let value = self.source.metrics().iter().map(|metric| sample.get(metric.clone())).fold(f32::MIN, f32::max);
+        self.check(value)
+    }
+
+    /// Compares the value to the limit, lowered by the hysteresis while the alarm is active.
+    fn check(&mut self, value: f32) -> bool {
+        let limit = if self.active { self.limit.saturating_sub(self.hysteresis) } else { self.limit };
+        self.active = value >= limit as f32;
+        self.blink = self.active && !self.blink;
+
+        self.active
+    }
+
+    /// Checks if the content should be hidden in the current update of a blinking alarm.
+    pub fn blink(&self) -> bool {
+        self.blink
+    }
+
+    /// Returns the temperature of the alarm source with the highest value.
+    pub fn hottest(&self, sample: &Sample) -> Metric {
+        match self.source {
+            AlarmSource::GpuTemperature => Metric::GpuTemperature,
+            AlarmSource::MaxTemperature if sample.get(Metric::GpuTemperature) > sample.get(Metric::CpuTemperature) => {
+                Metric::GpuTemperature
+            }
+            _ => Metric::CpuTemperature,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alarm(limit: u16, hysteresis: u16) -> Alarm {
+        Alarm {
+            enabled: true,
+            source: AlarmSource::CpuTemperature,
+            limit,
+            hysteresis,
+            effect: Some(AlarmEffect::Blink),
+            active: false,
+            blink: false,
+        }
+    }
+
+    #[test]
+    fn triggers_at_the_limit() {
+        let mut alarm = alarm(85, 0);
+        assert!(!alarm.check(84.9));
+        assert!(alarm.check(85.0));
+        assert!(!alarm.check(84.0));
+    }
+
+    #[test]
+    fn active_alarm_stays_within_hysteresis() {
+        let mut alarm = alarm(85, 5);
+        assert!(!alarm.check(82.0));
+        assert!(alarm.check(86.0));
+        assert!(alarm.check(82.0));
+        assert!(alarm.check(80.0));
+        assert!(!alarm.check(79.0));
+        assert!(!alarm.check(82.0));
+    }
+
+    #[test]
+    fn hysteresis_larger_than_the_limit() {
+        let mut alarm = alarm(3, 5);
+        assert!(alarm.check(3.0));
+        assert!(alarm.check(0.0));
+    }
+
+    #[test]
+    fn blinks_while_active() {
+        let mut alarm = alarm(85, 0);
+        alarm.check(90.0);
+        assert!(alarm.blink());
+        alarm.check(90.0);
+        assert!(!alarm.blink());
+        alarm.check(90.0);
+        assert!(alarm.blink());
+        alarm.check(50.0);
+        assert!(!alarm.blink());
+    }
+
+    #[test]
+    fn parses_the_configuration() {
+        let config = Config::default();
+        let alarm = Alarm::read(&config, true, 90, &[AlarmEffect::Invert, AlarmEffect::Blink]);
+        assert!(alarm.source == AlarmSource::CpuTemperature);
+        assert_eq!((alarm.limit, alarm.hysteresis), (90, 0));
+        assert!(alarm.effect == Some(AlarmEffect::Invert));
+        assert!(AlarmSource::get(AlarmSource::MaxTemperature.symbol()) == Some(AlarmSource::MaxTemperature));
+        assert!(AlarmEffect::get("blink_all") == Some(AlarmEffect::BlinkAll));
+    }
+}
@@ -1,23 +1,25 @@
 //! Display module for:
 //! - CH510 MESH DIGITAL
 
-use crate::monitor::{cpu::Cpu, gpu::Gpu};
-use super::{device_error, Mode};
+use crate::{monitor::sampler::{Metric, Sampler}, utils::config::Config};
+use super::{alarm::{Alarm, AlarmEffect}, device_error, Mode, ModeSelector};
 use hidapi::HidApi;
-use std::{thread::sleep, time::Duration};
+use std::time::Duration;
 
 pub const DEFAULT_MODE: Mode = Mode::Cpu;
+pub const TEMP_LIMIT_C: u8 = 90;
+pub const TEMP_LIMIT_F: u8 = 194;
 
 pub struct Display {
-    cpu: Cpu,
-    gpu: Gpu,
+    sampler: Sampler,
     pub mode: Mode,
+    selector: ModeSelector,
     update: Duration,
-    fahrenheit: bool,
+    pub alarm: Alarm,
 }
 
 impl Display {
-    pub fn new(cpu: Cpu, gpu: Gpu, mode: &Mode, update: Duration, fahrenheit: bool) -> Self {
+    pub fn new(mut sampler: Sampler, mode: &Mode, update: Duration, alarm: bool, config: &Config) -> Self {
         // Verify the display mode
         let mode = match mode {
             Mode::Default => DEFAULT_MODE,
@@ -26,12 +28,26 @@ impl Display {
             _ => mode.support_error(),
         };
 
+        // Verify the display modes of the rules & profiles
+        let selector = ModeSelector::new(&mode, &[], config);
+        selector.verify(|mode| matches!(mode, Mode::Cpu | Mode::Gpu), |_| false);
+        selector.metrics().into_iter().for_each(|metric| sampler.require(metric));
+
+        // Read the alarm settings
+        let alarm = Alarm::read(
+            config,
+            alarm,
+            if sampler.fahrenheit() { TEMP_LIMIT_F } else { TEMP_LIMIT_C },
+            &[AlarmEffect::ShowTemp, AlarmEffect::Blink],
+        );
+        alarm.require(&mut sampler);
+
         Display {
-            cpu,
-            gpu,
+            sampler,
             mode,
+            selector,
             update,
-            fahrenheit,
+            alarm,
         }
     }
 
@@ -40,44 +56,52 @@ impl Display {
         let device = api.open(vid, pid).unwrap_or_else(|_| device_error());
 
         // Display warning if a required module is missing
-        match self.mode {
-            Mode::Cpu => self.cpu.warn_temp(),
-            Mode::Gpu => self.gpu.warn_missing(),
-            _ => (),
+        if self.mode == Mode::Cpu || self.alarm.uses(Metric::CpuTemperature) {
+            self.sampler.cpu.warn_temp();
+        }
+        if self.alarm.uses(Metric::CpuPower) {
+            self.sampler.cpu.warn_rapl();
+        }
+        if self.mode == Mode::Gpu || self.alarm.uses(Metric::GpuTemperature) {
+            self.sampler.gpu.warn_missing();
         }
 
         // Get temperature unit
-        let unit = if self.fahrenheit { "F" } else { "C" };
+        let unit = if self.sampler.fahrenheit() { "F" } else { "C" };
 
         // Display loop
+        let mut selector = self.selector.clone();
+        let mut alarm = self.alarm.clone();
         loop {
-            let message = match self.mode {
-                Mode::Cpu => {
-                    // Get CPU instant & wait
-                    let cpu_instant = self.cpu.read_instant();
-                    sleep(self.update);
-
-                    // Return the message
-                    format!(
-                        "HLXDATA({},{},0,0,{})\r\n",
-                        self.cpu.get_usage(cpu_instant),
-                        self.cpu.get_temp(self.fahrenheit),
-                        unit,
-                    )
-                }
-                Mode::Gpu => {
-                    // Wait
-                    sleep(self.update);
-
-                    // Return the message
-                    format!(
-                        "HLXDATA({},{},0,0,{})\r\n",
-                        self.gpu.get_usage(),
-                        self.gpu.get_temp(self.fahrenheit),
-                        unit,
-                    )
+            // Get readings & select the display mode
+            let sample = self.sampler.sample(self.update);
+            let mut mode = selector.select(&sample);
+            if alarm.update(&sample) {
+                match alarm.effect {
+                    // Force the view of the overheating component
+                    Some(AlarmEffect::ShowTemp) => match alarm.hottest(&sample) {
+                        Metric::GpuTemperature => mode = Mode::Gpu,
+                        _ => mode = Mode::Cpu,
+                    },
+                    // Clear the values
+                    Some(AlarmEffect::Blink) if alarm.blink() => mode = Mode::Default,
+                    _ => (),
                 }
-                _ => "".to_owned(),
+            }
+            let message = match mode {
+                Mode::Cpu => format!(
+                    "HLXDATA({},{},0,0,{})\r\n",
+                    sample.get(Metric::CpuUsage).round() as u8,
+                    sample.get(Metric::CpuTemperature).round() as u8,
+                    unit,
+                ),
+                Mode::Gpu => format!(
+                    "HLXDATA({},{},0,0,{})\r\n",
+                    sample.get(Metric::GpuUsage).round() as u8,
+                    sample.get(Metric::GpuTemperature).round() as u8,
+                    unit,
+                ),
+                _ => format!("HLXDATA(0,0,0,0,{})\r\n", unit),
             };
             device.write(message.as_bytes()).unwrap();
         }
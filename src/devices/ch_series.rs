@@ -3,51 +3,116 @@
 //! - CH560 DIGITAL
 //! - MORPHEUS
 
-use crate::monitor::{cpu::Cpu, gpu::Gpu};
-use super::{device_error, Mode, AUTO_MODE_INTERVAL};
+use crate::{monitor::{external, hwmon, sampler::{Metric, Sample, Sampler}}, utils::config::Config};
+use super::{alarm::{Alarm, AlarmEffect}, device_error, status_bar::StatusBar, Mode, ModeSelector};
 use hidapi::HidApi;
-use std::{thread::sleep, time::{Duration, Instant}};
+use std::time::Duration;
 
 pub const DEFAULT_MODE: Mode = Mode::CpuTemperature;
+pub const AUTO_MODES: [Mode; 2] = [Mode::CpuTemperature, Mode::CpuUsage];
+pub const SECONDARY_AUTO_MODES: [Mode; 2] = [Mode::GpuTemperature, Mode::GpuUsage];
+pub const TEMP_LIMIT_C: u8 = 90;
+pub const TEMP_LIMIT_F: u8 = 194;
 
 pub struct Display {
-    cpu: Cpu,
-    gpu: Gpu,
+    sampler: Sampler,
     pub mode: Mode,
     pub secondary: Mode,
+    selector: ModeSelector,
     update: Duration,
-    fahrenheit: bool,
+    pub alarm: Alarm,
+    status_bar: StatusBar,
+    secondary_status_bar: StatusBar,
 }
 
 impl Display {
-    pub fn new(cpu: Cpu, gpu: Gpu, mode: &Mode, secondary: &Mode, update: Duration, fahrenheit: bool) -> Self {
+    pub fn new(
+        mut sampler: Sampler,
+        mode: &Mode,
+        secondary: &Mode,
+        update: Duration,
+        alarm: bool,
+        config: &Config,
+    ) -> Self {
         // Verify the display mode
+        let supported = |mode: &Mode| match mode {
+            Mode::Hwmon(symbol) => hwmon::unit(symbol) == hwmon::Unit::Temperature,
+            Mode::External => matches!(external::unit(), external::Unit::Percent | external::Unit::Celsius),
+            _ => matches!(
+                mode,
+                Mode::Auto
+                    | Mode::CpuTemperature
+                    | Mode::CpuUsage
+                    | Mode::CpuMaxCoreUsage
+                    | Mode::CpuPerformanceUsage
+                    | Mode::CpuEfficiencyUsage
+                    | Mode::CpuActiveUsage
+                    | Mode::CgroupUsage
+                    | Mode::GpuTemperature
+                    | Mode::GpuUsage
+                    | Mode::RamUsage
+                    | Mode::SwapUsage
+                    | Mode::DiskTemperature
+                    | Mode::BoardTemperature
+            ),
+        };
         let mode = match mode {
             Mode::Default => DEFAULT_MODE,
-            Mode::Auto => Mode::Auto,
-            Mode::CpuTemperature => Mode::CpuTemperature,
-            Mode::CpuUsage => Mode::CpuUsage,
+            _ if supported(mode) => mode.clone(),
             _ => mode.support_error(),
         };
 
         let secondary = match secondary {
             Mode::Default => match mode {
-                Mode::CpuTemperature => Mode::GpuTemperature,
-                Mode::CpuUsage => Mode::GpuUsage,
+                Mode::CpuTemperature | Mode::DiskTemperature | Mode::BoardTemperature => Mode::GpuTemperature,
+                Mode::CpuUsage
+                | Mode::CpuMaxCoreUsage
+                | Mode::CpuPerformanceUsage
+                | Mode::CpuEfficiencyUsage
+                | Mode::CpuActiveUsage
+                | Mode::CgroupUsage
+                | Mode::RamUsage
+                | Mode::SwapUsage => Mode::GpuUsage,
+                Mode::GpuTemperature => Mode::CpuTemperature,
+                Mode::GpuUsage => Mode::CpuUsage,
                 _ => Mode::Auto,
             },
-            Mode::GpuTemperature => Mode::GpuTemperature,
-            Mode::GpuUsage => Mode::GpuUsage,
+            _ if supported(secondary) => secondary.clone(),
             _ => secondary.support_error_secondary(),
         };
 
+        // Verify the display modes of the rules & profiles
+        let selector = ModeSelector::new(&mode, &AUTO_MODES, config).with_secondary(&secondary, &SECONDARY_AUTO_MODES);
+        selector.verify(supported, supported);
+        selector.metrics().into_iter().for_each(|metric| sampler.require(metric));
+
+        // Take the initial readings (e.g. core times) only for the displayed metrics
+        selector.mode_metrics().into_iter().for_each(|metric| sampler.require(metric));
+
+        // Read the alarm settings
+        let alarm = Alarm::read(
+            config,
+            alarm,
+            if sampler.fahrenheit() { TEMP_LIMIT_F } else { TEMP_LIMIT_C },
+            &[AlarmEffect::ShowTemp, AlarmEffect::Blink],
+        );
+        alarm.require(&mut sampler);
+
+        // Read the status bar settings
+        let status_bar = StatusBar::read(config, "status_bar", Metric::CpuUsage, sampler.fahrenheit());
+        let secondary_status_bar = StatusBar::read(config, "secondary_status_bar", Metric::GpuUsage, sampler.fahrenheit());
+        sampler.require(status_bar.metric.clone());
+        sampler.require(secondary_status_bar.metric.clone());
+
         Display {
-            cpu,
-            gpu,
+            sampler,
             mode,
             secondary,
+            selector,
             update,
-            fahrenheit,
+            alarm,
+            status_bar,
+            secondary_status_bar,
         }
     }
 
@@ -56,10 +121,24 @@ impl Display {
         let device = api.open(vid, pid).unwrap_or_else(|_| device_error());
 
         // Display warning if a required module is missing
-        if matches!(self.mode, Mode::CpuTemperature) {
-            self.cpu.warn_temp();
+        if
+            self.selector.modes().contains(&&Mode::CpuTemperature) ||
+            self.selector.secondary_modes().contains(&&Mode::CpuTemperature) ||
+            self.alarm.uses(Metric::CpuTemperature) ||
+            self.status_bar.metric == Metric::CpuTemperature ||
+            self.secondary_status_bar.metric == Metric::CpuTemperature
+        {
+            self.sampler.cpu.warn_temp();
         }
-        self.gpu.warn_missing();
+        if
+            self.alarm.uses(Metric::CpuPower) ||
+            self.status_bar.metric == Metric::CpuPower ||
+            self.secondary_status_bar.metric == Metric::CpuPower
+        {
+            self.sampler.cpu.warn_rapl();
+        }
+        self.sampler.gpu.warn_missing();
+        self.selector.mode_metrics().into_iter().for_each(|metric| self.sampler.warn_missing_sensor(metric));
 
         // Data packet
         let mut data: [u8; 64] = [0; 64];
@@ -73,109 +152,84 @@ impl Display {
         }
 
         // Display loop
-        match self.mode {
-            Mode::Auto => {
-                let mut initial_update = self.update;
-                let mut mode = Mode::CpuTemperature;
-                loop {
-                    // Initial update
-                    device.write(&self.status_message(&data, &mode, initial_update)).unwrap();
-
-                    // Update until timeout
-                    let timeout = Instant::now() + AUTO_MODE_INTERVAL;
-                    while Instant::now() + self.update < timeout {
-                        device.write(&self.status_message(&data, &mode, self.update)).unwrap();
+        let mut selector = self.selector.clone();
+        let mut alarm = self.alarm.clone();
+        loop {
+            let sample = self.sampler.sample(selector.update(self.update));
+            let mut mode = selector.select(&sample);
+            let mut secondary = selector.secondary().unwrap_or(self.secondary.clone());
+            if alarm.update(&sample) {
+                match alarm.effect {
+                    Some(AlarmEffect::ShowTemp) => {
+                        // Force the temperature view of the overheating component
+                        if alarm.uses(Metric::CpuTemperature) || alarm.uses(Metric::CpuPower) {
+                            mode = Mode::CpuTemperature;
+                        }
+                        if alarm.uses(Metric::GpuTemperature) {
+                            secondary = Mode::GpuTemperature;
+                        }
                     }
-
-                    // Make the next initial update faster to fit the timeframe
-                    initial_update = timeout - Instant::now();
-
-                    // Switch to the next display mode
-                    mode = match mode {
-                        Mode::CpuTemperature => Mode::CpuUsage,
-                        Mode::CpuUsage => Mode::CpuTemperature,
-                        _ => DEFAULT_MODE,
+                    Some(AlarmEffect::Blink) if alarm.blink() => {
+                        device.write(&data).unwrap();
+                        continue;
                     }
+                    _ => (),
                 }
             }
-            _ => loop {
-                device.write(&self.status_message(&data, &self.mode, self.update)).unwrap();
-            }
+            device.write(&self.status_message(&data, &mode, &secondary, &sample)).unwrap();
         }
     }
 
-    /// Reads the CPU status information and returns the data packet.
-    fn status_message(&self, inital_data: &[u8; 64], mode: &Mode, update: Duration) -> [u8; 64] {
+    /// Reads the system status information and returns the data packet.
+    fn status_message(&self, inital_data: &[u8; 64], mode: &Mode, secondary: &Mode, sample: &Sample) -> [u8; 64] {
         // Clone the data packet
         let mut data = inital_data.clone();
 
-        // Read CPU utilization
-        let cpu_instant = self.cpu.read_instant();
+        // Main display (values above 999 are displayed as 999)
+        let (unit, value) = self.readout(mode, sample);
+        let value = value.min(999);
+        data[1] = unit;
+        data[3] = (value / 100) as u8;
+        data[4] = (value % 100 / 10) as u8;
+        data[5] = (value % 10) as u8;
+
+        // Secondary display
+        let (unit, value) = self.readout(secondary, sample);
+        let value = value.min(999);
+        data[6] = unit;
+        data[8] = (value / 100) as u8;
+        data[9] = (value % 100 / 10) as u8;
+        data[10] = (value % 10) as u8;
 
-        // Wait
-        sleep(update);
+        // Status bar
+        data[2] = self.status_bar.segments(sample);
+        data[7] = self.secondary_status_bar.segments(sample);
 
-        // Calculate CPU & GPU usage
-        let cpu_usage = self.cpu.get_usage(cpu_instant);
-        let gpu_usage = self.gpu.get_usage();
+        data
+    }
 
-        // Main display
+    /// Returns the unit byte and the value of the display mode.
+    fn readout(&self, mode: &Mode, sample: &Sample) -> (u8, u16) {
+        let temp_unit = if self.sampler.fahrenheit() { 35 } else { 19 };
         match mode {
-            Mode::CpuTemperature => {
-                // CPU
-                let unit = if self.fahrenheit { 35 } else { 19 };
-                let cpu_temp = self.cpu.get_temp(self.fahrenheit);
-                data[1] = unit;
-                data[3] = cpu_temp / 100;
-                data[4] = cpu_temp % 100 / 10;
-                data[5] = cpu_temp % 10;
-                // GPU
-                if self.secondary == Mode::Auto {
-                    let gpu_temp = self.gpu.get_temp(self.fahrenheit);
-                    data[6] = unit;
-                    data[8] = gpu_temp / 100;
-                    data[9] = gpu_temp % 100 / 10;
-                    data[10] = gpu_temp % 10;
-                }
-            }
-            Mode::CpuUsage => {
-                // CPU
-                data[1] = 76;
-                data[3] = cpu_usage / 100;
-                data[4] = cpu_usage % 100 / 10;
-                data[5] = cpu_usage % 10;
-                // GPU
-                if self.secondary == Mode::Auto {
-                    data[6] = 76;
-                    data[8] = gpu_usage / 100;
-                    data[9] = gpu_usage % 100 / 10;
-                    data[10] = gpu_usage % 10;
-                }
-            }
-            _ => (),
+            Mode::CpuTemperature => (temp_unit, sample.get(Metric::CpuTemperature).round() as u16),
+            Mode::CpuUsage => (76, sample.get(Metric::CpuUsage).round() as u16),
+            Mode::GpuTemperature => (temp_unit, sample.get(Metric::GpuTemperature).round() as u16),
+            Mode::GpuUsage => (76, sample.get(Metric::GpuUsage).round() as u16),
+            Mode::RamUsage => (76, sample.get(Metric::RamUsage).round() as u16),
+            Mode::SwapUsage => (76, sample.get(Metric::SwapUsage).round() as u16),
+            Mode::DiskTemperature => (temp_unit, sample.get(Metric::DiskTemperature).round() as u16),
+            Mode::BoardTemperature => (temp_unit, sample.get(Metric::BoardTemperature).round() as u16),
+            Mode::Hwmon(_) | Mode::External => (
+                if mode.is_temperature() { temp_unit } else { 76 },
+                sample.get(mode.metric().unwrap()).round() as u16,
+            ),
+            Mode::CpuMaxCoreUsage
+            | Mode::CpuPerformanceUsage
+            | Mode::CpuEfficiencyUsage
+            | Mode::CpuActiveUsage
+            | Mode::CgroupUsage => (76, sample.get(mode.metric().unwrap()).round() as u16),
+            _ => (0, 0),
         }
-        if data[6] == 0 {
-            match self.secondary {
-                Mode::GpuTemperature => {
-                    let gpu_temp = self.gpu.get_temp(self.fahrenheit);
-                    data[6] = if self.fahrenheit { 35 } else { 19 };
-                    data[8] = gpu_temp / 100;
-                    data[9] = gpu_temp % 100 / 10;
-                    data[10] = gpu_temp % 10;
-                }
-                Mode::GpuUsage => {
-                    data[6] = 76;
-                    data[8] = gpu_usage / 100;
-                    data[9] = gpu_usage % 100 / 10;
-                    data[10] = gpu_usage % 10;
-                }
-                _ => (),
-            }
-        }
-        // Status bar
-        data[2] = if cpu_usage < 15 { 1 } else { (cpu_usage as f32 / 10.0).round() as u8 };
-        data[7] = if gpu_usage < 15 { 1 } else { (gpu_usage as f32 / 10.0).round() as u8 };
-
-        data
     }
 }
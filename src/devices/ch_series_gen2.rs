@@ -3,23 +3,27 @@
 //! - CH270 DIGITAL
 //! - CH690 DIGITAL
 
-use crate::{monitor::{cpu::Cpu, gpu::Gpu}, warning};
-use super::{device_error, Mode, AUTO_MODE_INTERVAL};
+use crate::{monitor::sampler::{Metric, Sample, Sampler}, utils::config::Config};
+use super::{alarm::{Alarm, AlarmEffect}, device_error, layout::{Field, Layout}, Mode, ModeSelector};
 use hidapi::HidApi;
-use std::{thread::sleep, time::{Duration, Instant}};
+use std::time::Duration;
 
 pub const DEFAULT_MODE: Mode = Mode::CpuFrequency;
+pub const AUTO_MODES: [Mode; 4] = [Mode::CpuFrequency, Mode::CpuFan, Mode::Gpu, Mode::Psu];
+pub const TEMP_LIMIT_C: u8 = 90;
+pub const TEMP_LIMIT_F: u8 = 194;
 
 pub struct Display {
-    cpu: Cpu,
-    gpu: Gpu,
+    sampler: Sampler,
     pub mode: Mode,
+    selector: ModeSelector,
     update: Duration,
-    fahrenheit: bool,
+    pub alarm: Alarm,
+    layout: Layout,
 }
 
 impl Display {
-    pub fn new(cpu: Cpu, gpu: Gpu, mode: &Mode, update: Duration, fahrenheit: bool) -> Self {
+    pub fn new(mut sampler: Sampler, mode: &Mode, update: Duration, alarm: bool, config: &Config) -> Self {
         // Verify the display mode
         let mode = match mode {
             Mode::Default => DEFAULT_MODE,
@@ -31,12 +35,47 @@ impl Display {
             _ => mode.support_error(),
         };
 
+        // Verify the display modes of the rules & profiles
+        // Only cycle through the modes with available sensors
+        let cycle: Vec<Mode> = AUTO_MODES
+            .into_iter()
+            .filter(|mode| match mode {
+                Mode::CpuFan => sampler.fan.is_found(),
+                Mode::Psu => sampler.psu.is_found(),
+                _ => true,
+            })
+            .collect();
+        let selector = ModeSelector::new(&mode, &cycle, config);
+        selector.verify(
+            |mode| matches!(mode, Mode::Auto | Mode::CpuFrequency | Mode::CpuFan | Mode::Gpu | Mode::Psu),
+            |_| false,
+        );
+        selector.metrics().into_iter().for_each(|metric| sampler.require(metric));
+
+        // Read the source of the power field in the CPU view
+        let layout = Layout::read(config, &[Field::Power]);
+
+        // Read the energy consumption only if it is displayed
+        if selector.modes().iter().any(|mode| matches!(mode, Mode::CpuFrequency | Mode::CpuFan)) {
+            layout.require(&mut sampler);
+        }
+
+        // Read the alarm settings
+        let alarm = Alarm::read(
+            config,
+            alarm,
+            if sampler.fahrenheit() { TEMP_LIMIT_F } else { TEMP_LIMIT_C },
+            &[AlarmEffect::ShowTemp, AlarmEffect::Blink],
+        );
+        alarm.require(&mut sampler);
+
         Display {
-            cpu,
-            gpu,
+            sampler,
             mode,
+            selector,
             update,
-            fahrenheit,
+            alarm,
+            layout,
         }
     }
 
@@ -44,21 +83,25 @@ impl Display {
         // Connect to device
         let device = api.open(vid, pid).unwrap_or_else(|_| device_error());
 
-        // Display warning to address limitated display modes
-        match self.mode {
-            Mode::CpuFan => { warning!("CPU fan speed monitoring is not yet supported"); }
-            Mode::Psu => { warning!("PSU monitoring is not yet supported"); }
-            Mode::Auto => { warning!("Display mode \"auto\" only cycles between fully supported modes"); }
-            _ => (),
-        }
-
         // Display warning if a required module is missing
-        if matches!(self.mode, Mode::CpuFrequency | Mode::CpuFan | Mode::Auto) {
-            self.cpu.warn_temp();
-            self.cpu.warn_rapl();
+        let cpu_view = matches!(self.mode, Mode::CpuFrequency | Mode::CpuFan | Mode::Auto);
+        if cpu_view || self.alarm.uses(Metric::CpuTemperature) {
+            self.sampler.cpu.warn_temp();
+        }
+        if cpu_view {
+            self.layout.warn_missing(&self.sampler);
+        }
+        if self.alarm.uses(Metric::CpuPower) && !(cpu_view && self.layout.get(Field::Power) == Metric::CpuPower) {
+            self.sampler.cpu.warn_rapl();
+        }
+        if matches!(self.mode, Mode::CpuFan) {
+            self.sampler.fan.warn_missing();
+        }
+        if matches!(self.mode, Mode::Psu) {
+            self.sampler.psu.warn_missing();
         }
-        if matches!(self.mode, Mode::Gpu | Mode::Auto) {
-            self.gpu.warn_missing();
+        if matches!(self.mode, Mode::Gpu | Mode::Auto) || self.alarm.uses(Metric::GpuTemperature) {
+            self.sampler.gpu.warn_missing();
         }
 
         // Data packet
@@ -69,42 +112,33 @@ impl Display {
         data[3] = 6;
         data[4] = 35;
         data[5] = 1;
-        data[9] = if self.fahrenheit { 1 } else { 0 };
+        data[9] = if self.sampler.fahrenheit() { 1 } else { 0 };
 
         // Display loop
-        match self.mode {
-            Mode::Auto => {
-                let mut initial_update = self.update;
-                let mut mode = Mode::CpuFrequency;
-                loop {
-                    // Initial update
-                    device.write(&self.status_message(&data, &mode, initial_update)).unwrap();
-
-                    // Update until timeout
-                    let timeout = Instant::now() + AUTO_MODE_INTERVAL;
-                    while Instant::now() + self.update < timeout {
-                        device.write(&self.status_message(&data, &mode, self.update)).unwrap();
-                    }
-
-                    // Make the next initial update faster to fit the timeframe
-                    initial_update = timeout - Instant::now();
-
-                    // Switch to the next display mode
-                    mode = match mode {
-                        Mode::CpuFrequency => Mode::Gpu,
-                        Mode::Gpu => Mode::CpuFrequency,
-                        _ => DEFAULT_MODE,
-                    }
+        let mut selector = self.selector.clone();
+        let mut alarm = self.alarm.clone();
+        loop {
+            let sample = self.sampler.sample(selector.update(self.update));
+            let mut mode = selector.select(&sample);
+            if alarm.update(&sample) {
+                match alarm.effect {
+                    // Force the view of the overheating component
+                    Some(AlarmEffect::ShowTemp) => match alarm.hottest(&sample) {
+                        Metric::GpuTemperature => mode = Mode::Gpu,
+                        _ if !matches!(mode, Mode::CpuFrequency | Mode::CpuFan) => mode = Mode::CpuFrequency,
+                        _ => (),
+                    },
+                    // Clear the display
+                    Some(AlarmEffect::Blink) if alarm.blink() => mode = Mode::Default,
+                    _ => (),
                 }
             }
-            _ => loop {
-                device.write(&self.status_message(&data, &self.mode, self.update)).unwrap();
-            }
+            device.write(&self.status_message(&data, &mode, &sample)).unwrap();
         }
     }
 
     /// Reads the system status information and returns the data packet.
-    fn status_message(&self, inital_data: &[u8; 64], mode: &Mode, update: Duration) -> [u8; 64] {
+    fn status_message(&self, inital_data: &[u8; 64], mode: &Mode, sample: &Sample) -> [u8; 64] {
         // Clone the data packet
         let mut data = inital_data.clone();
 
@@ -113,69 +147,87 @@ impl Display {
             Mode::CpuFrequency => 2,
             Mode::CpuFan => 3,
             Mode::Gpu => 4,
-            Mode::Psu => 5,
+            Mode::Psu if self.sampler.psu.has_fan() => 5,
+            Mode::Psu => 6,
             _ => 0,
         };
 
         // Main display
         match mode {
             Mode::CpuFrequency | Mode::CpuFan => {
-                // Read CPU utilization & energy consumption
-                let cpu_instant = self.cpu.read_instant();
-                let cpu_energy = self.cpu.read_energy();
-
-                // Wait
-                sleep(update);
-
                 // Power consumption
-                let power = (self.cpu.get_power(cpu_energy, update.as_millis() as u64)).to_be_bytes();
+                let power = (sample.get(self.layout.get(Field::Power)).round() as u16).to_be_bytes();
                 data[7] = power[0];
                 data[8] = power[1];
 
                 // Temperature
-                let temp = (self.cpu.get_temp(self.fahrenheit) as f32).to_be_bytes();
+                let temp = sample.get(Metric::CpuTemperature).to_be_bytes();
                 data[10] = temp[0];
                 data[11] = temp[1];
                 data[12] = temp[2];
                 data[13] = temp[3];
 
                 // Utilization
-                data[14] = self.cpu.get_usage(cpu_instant);
+                data[14] = sample.get(Metric::CpuUsage).round() as u8;
 
-                // Frequency
+                // Frequency or fan speed
                 if matches!(mode, Mode::CpuFrequency) {
-                    let frequency = (self.cpu.get_frequency()).to_be_bytes();
+                    let frequency = (sample.get(Metric::CpuFrequency).round() as u16).to_be_bytes();
                     data[15] = frequency[0];
                     data[16] = frequency[1];
+                } else {
+                    let fan_speed = (sample.get(Metric::CpuFan).round() as u16).to_be_bytes();
+                    data[17] = fan_speed[0];
+                    data[18] = fan_speed[1];
                 }
             }
             Mode::Gpu => {
-                // Wait
-                sleep(update);
-
                 // Power consumption
-                let power = (self.gpu.get_power()).to_be_bytes();
+                let power = (sample.get(Metric::GpuPower).round() as u16).to_be_bytes();
                 data[19] = power[0];
                 data[20] = power[1];
 
                 // Temperature
-                let temp = (self.gpu.get_temp(self.fahrenheit) as f32).to_be_bytes();
+                let temp = sample.get(Metric::GpuTemperature).to_be_bytes();
                 data[21] = temp[0];
                 data[22] = temp[1];
                 data[23] = temp[2];
                 data[24] = temp[3];
 
                 // Utilization
-                data[25] = self.gpu.get_usage();
+                data[25] = sample.get(Metric::GpuUsage).round() as u8;
 
                 // Frequency
-                let frequency = (self.gpu.get_frequency()).to_be_bytes();
+                let frequency = (sample.get(Metric::GpuFrequency).round() as u16).to_be_bytes();
                 data[26] = frequency[0];
                 data[27] = frequency[1];
             }
             Mode::Psu => {
-                // Wait
-                sleep(update);
+                // Power consumption
+                let power = (sample.get(Metric::PsuPower).round() as u16).to_be_bytes();
+                data[28] = power[0];
+                data[29] = power[1];
+
+                // Temperature
+                let temp = sample.get(Metric::PsuTemperature).to_be_bytes();
+                data[30] = temp[0];
+                data[31] = temp[1];
+                data[32] = temp[2];
+                data[33] = temp[3];
+
+                // Load
+                data[34] = sample.get(Metric::PsuUsage).round() as u8;
+
+                // Fan speed or input power
+                if self.sampler.psu.has_fan() {
+                    let fan_speed = (sample.get(Metric::PsuFan).round() as u16).to_be_bytes();
+                    data[37] = fan_speed[0];
+                    data[38] = fan_speed[1];
+                } else {
+                    let input_power = (sample.get(Metric::PsuInputPower).round() as u16).to_be_bytes();
+                    data[35] = input_power[0];
+                    data[36] = input_power[1];
+                }
             }
             _ => (),
         }
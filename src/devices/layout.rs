@@ -0,0 +1,176 @@
+//! Maps the metrics to the fixed fields of the LD, LQ, AK PRO, and CH (2nd generation) displays.
+//!
+//! The fields show the CPU readings by default, their sources can be changed in the configuration file:
+//! ```ini
+//! [layout]
+//! power = gpu_power
+//! temp = coolant_temp
+//! usage = gpu_usage
+//! freq = gpu_freq
+//! ```
+
+use crate::{error, monitor::{external, hwmon, sampler::{Metric, Sampler}}, utils::config::Config};
+use std::process::exit;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Field {
+    Power,
+    Temperature,
+    Usage,
+    Frequency,
+}
+
+impl Field {
+    const fn symbol(&self) -> &'static str {
+        match self {
+            Field::Power => "power",
+            Field::Temperature => "temp",
+            Field::Usage => "usage",
+            Field::Frequency => "freq",
+        }
+    }
+
+    fn get(symbol: &str) -> Option<Field> {
+        match symbol {
+            "power" => Some(Self::Power),
+            "temp" => Some(Self::Temperature),
+            "usage" => Some(Self::Usage),
+            "freq" => Some(Self::Frequency),
+            _ => None,
+        }
+    }
+
+    const fn default_metric(&self) -> Metric {
+        match self {
+            Field::Power => Metric::CpuPower,
+            Field::Temperature => Metric::CpuTemperature,
+            Field::Usage => Metric::CpuUsage,
+            Field::Frequency => Metric::CpuFrequency,
+        }
+    }
+
+    /// Checks if the metric has the unit of the field.
+    fn accepts(&self, metric: &Metric) -> bool {
+        if let Metric::Hwmon(symbol) = metric {
+            return match hwmon::unit(symbol) {
+                hwmon::Unit::Power => *self == Field::Power,
+                hwmon::Unit::Temperature => *self == Field::Temperature,
+                _ => false,
+            };
+        }
+        if *metric == Metric::External {
+            return match external::unit() {
+                external::Unit::Percent => *self == Field::Usage,
+                external::Unit::Celsius => *self == Field::Temperature,
+                external::Unit::Watt => *self == Field::Power,
+                _ => false,
+            };
+        }
+
+        match self {
+            Field::Power => matches!(
+                metric,
+                Metric::CpuPower
+                    | Metric::CpuCorePower
+                    | Metric::GpuPower
+                    | Metric::DramPower
+                    | Metric::SystemPower
+                    | Metric::PsuPower
+                    | Metric::PsuInputPower
+            ),
+            Field::Temperature => matches!(
+                metric,
+                Metric::CpuTemperature
+                    | Metric::GpuTemperature
+                    | Metric::PsuTemperature
+                    | Metric::CoolantTemperature
+                    | Metric::DiskTemperature
+                    | Metric::BoardTemperature
+            ),
+            Field::Usage => matches!(
+                metric,
+                Metric::CpuUsage
+                    | Metric::CpuMaxCoreUsage
+                    | Metric::CpuPerformanceUsage
+                    | Metric::CpuEfficiencyUsage
+                    | Metric::CpuActiveUsage
+                    | Metric::CgroupUsage
+                    | Metric::GpuUsage
+                    | Metric::PsuUsage
+                    | Metric::RamUsage
+                    | Metric::SwapUsage
+            ),
+            Field::Frequency => matches!(metric, Metric::CpuFrequency | Metric::GpuFrequency),
+        }
+    }
+}
+
+pub struct Layout {
+    fields: Vec<(Field, Metric)>,
+}
+
+impl Layout {
+    /// Reads the `[layout]` section for the fields of the device.
+    pub fn read(config: &Config, fields: &[Field]) -> Self {
+        let section = config.sections("layout").last();
+        if let Some(section) = section {
+            for key in section.keys() {
+                match Field::get(key) {
+                    Some(field) if fields.contains(&field) => (),
+                    _ => {
+                        error!(format!("Field \"{key}\" in [layout] is not supported on your device"));
+                        exit(1);
+                    }
+                }
+            }
+        }
+
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let metric = match section.and_then(|s| s.get(field.symbol()).map(|symbol| (s, symbol))) {
+                    Some((section, symbol)) => match Metric::get(symbol) {
+                        Some(metric) if field.accepts(&metric) => metric,
+                        _ => section.value_error(field.symbol(), symbol),
+                    },
+                    None => field.default_metric(),
+                };
+                (*field, metric)
+            })
+            .collect();
+
+        Layout { fields }
+    }
+
+    /// Returns the metric displayed in the field.
+    pub fn get(&self, field: Field) -> Metric {
+        self.fields
+            .iter()
+            .find(|(f, _)| *f == field)
+            .map_or(field.default_metric(), |(_, metric)| metric.clone())
+    }
+
+    /// Makes sure that the metrics of all fields are read by the sampler.
+    pub fn require(&self, sampler: &mut Sampler) {
+        self.fields.iter().for_each(|(_, metric)| sampler.require(metric.clone()));
+    }
+
+    /// Displays a warning message for each missing sensor of the fields.
+    pub fn warn_missing(&self, sampler: &Sampler) {
+        let uses = |metrics: &[Metric]| self.fields.iter().any(|(_, metric)| metrics.contains(metric));
+        if uses(&[Metric::CpuTemperature]) {
+            sampler.cpu.warn_temp();
+        }
+        self.fields
+            .iter()
+            .filter_map(|(_, metric)| metric.domain())
+            .for_each(|domain| sampler.cpu.warn_power(domain));
+        if uses(&[Metric::GpuTemperature, Metric::GpuUsage, Metric::GpuPower, Metric::GpuFrequency]) {
+            sampler.gpu.warn_missing();
+        }
+        if uses(&[Metric::PsuTemperature, Metric::PsuPower, Metric::PsuInputPower, Metric::PsuUsage]) {
+            sampler.psu.warn_missing();
+        }
+        self.fields.iter().for_each(|(_, metric)| sampler.warn_missing_sensor(metric.clone()));
+    }
+}
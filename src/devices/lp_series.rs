@@ -2,11 +2,10 @@
 //! - LP240
 //! - LP360
 
-use crate::monitor::{cpu::Cpu, gpu::Gpu};
-use super::{device_error, Mode};
-use cpu_monitor::CpuInstant;
+use crate::{monitor::{external, hwmon, sampler::{Metric, Sample, Sampler}}, utils::config::Config};
+use super::{alarm::{Alarm, AlarmEffect}, device_error, Mode, ModeSelector};
 use hidapi::HidApi;
-use std::{thread::sleep, time::Duration};
+use std::time::Duration;
 
 /// Helper module for the LP Series.
 mod dot_matrix {
@@ -15,6 +14,7 @@ mod dot_matrix {
         Celsius,
         Fahrenheit,
         Watt,
+        Gigabyte,
         Empty,
     }
 
@@ -50,6 +50,13 @@ mod dot_matrix {
                     [true, false, true, false, true],
                     [false, true, false, true, false],
                 ],
+                Unit::Gigabyte => [
+                    [false, true, true, true, false],
+                    [true, false, false, false, false],
+                    [true, false, true, true, false],
+                    [true, false, false, true, false],
+                    [false, true, true, true, false],
+                ],
                 Unit::Empty => [[false; 5]; 5],
             }
         }
@@ -217,50 +224,96 @@ mod dot_matrix {
 }
 
 pub const DEFAULT_MODE: Mode = Mode::CpuUsage;
+pub const TEMP_LIMIT_C: u8 = 90;
+pub const TEMP_LIMIT_F: u8 = 194;
 
 pub struct Display {
-    cpu: Cpu,
-    gpu: Gpu,
+    sampler: Sampler,
     pub mode: Mode,
     pub secondary: Option<Mode>,
+    selector: ModeSelector,
     update: Duration,
-    fahrenheit: bool,
     rotate: u16,
+    pub alarm: Alarm,
 }
 
 impl Display {
-    pub fn new(cpu: Cpu, gpu: Gpu, mode: &Mode, secondary: &Mode, update: Duration, fahrenheit: bool, rotate: u16) -> Self {
+    pub fn new(
+        mut sampler: Sampler,
+        mode: &Mode,
+        secondary: &Mode,
+        update: Duration,
+        rotate: u16,
+        alarm: bool,
+        config: &Config,
+    ) -> Self {
         // Verify the display mode
+        let supported = |mode: &Mode| match mode {
+            Mode::Hwmon(symbol) => matches!(hwmon::unit(symbol), hwmon::Unit::Temperature | hwmon::Unit::Power),
+            _ => matches!(
+                mode,
+                Mode::CpuUsage
+                    | Mode::CpuTemperature
+                    | Mode::CpuPower
+                    | Mode::CpuCorePower
+                    | Mode::GpuUsage
+                    | Mode::GpuTemperature
+                    | Mode::GpuPower
+                    | Mode::DramPower
+                    | Mode::SystemPower
+                    | Mode::CpuMaxCoreUsage
+                    | Mode::CpuPerformanceUsage
+                    | Mode::CpuEfficiencyUsage
+                    | Mode::CpuActiveUsage
+                    | Mode::CgroupUsage
+                    | Mode::RamUsage
+                    | Mode::RamUsed
+                    | Mode::SwapUsage
+                    | Mode::DiskTemperature
+                    | Mode::BoardTemperature
+                    | Mode::External
+            ),
+        };
         let mode = match mode {
             Mode::Default => DEFAULT_MODE,
-            Mode::CpuUsage => Mode::CpuUsage,
-            Mode::CpuTemperature => Mode::CpuTemperature,
-            Mode::CpuPower => Mode::CpuPower,
-            Mode::GpuUsage => Mode::GpuUsage,
-            Mode::GpuTemperature => Mode::GpuTemperature,
-            Mode::GpuPower => Mode::GpuPower,
+            _ if supported(mode) => mode.clone(),
             _ => mode.support_error(),
         };
 
         let secondary = match secondary {
             Mode::Default => None,
-            Mode::CpuUsage => Some(Mode::CpuUsage),
-            Mode::CpuTemperature => Some(Mode::CpuTemperature),
-            Mode::CpuPower => Some(Mode::CpuPower),
-            Mode::GpuUsage => Some(Mode::GpuUsage),
-            Mode::GpuTemperature => Some(Mode::GpuTemperature),
-            Mode::GpuPower => Some(Mode::GpuPower),
+            _ if supported(secondary) => Some(secondary.clone()),
             _ => Some(secondary.support_error_secondary()),
         };
 
+        // Verify the display modes of the rules & profiles
+        let selector = ModeSelector::new(&mode, &[], config);
+        selector.verify(supported, supported);
+        selector.metrics().into_iter().for_each(|metric| sampler.require(metric));
+
+        // Take the initial readings (e.g. energy consumption) only for the displayed metrics
+        selector.mode_metrics().into_iter().for_each(|metric| sampler.require(metric));
+        if let Some(metric) = secondary.as_ref().and_then(Mode::metric) {
+            sampler.require(metric);
+        }
+
+        // Read the alarm settings
+        let alarm = Alarm::read(
+            config,
+            alarm,
+            if sampler.fahrenheit() { TEMP_LIMIT_F } else { TEMP_LIMIT_C },
+            &[AlarmEffect::Blink, AlarmEffect::BlinkAll, AlarmEffect::Invert],
+        );
+        alarm.require(&mut sampler);
+
         Display {
-            cpu,
-            gpu,
+            sampler,
             mode,
             secondary,
+            selector,
             update,
-            fahrenheit,
             rotate,
+            alarm,
         }
     }
 
@@ -269,18 +322,35 @@ impl Display {
         let device = api.open(vid, pid).unwrap_or_else(|_| device_error());
 
         // Display warning if a required module is missing
-        if matches!(self.mode, Mode::CpuTemperature) || matches!(self.secondary, Some(Mode::CpuTemperature)) {
-            self.cpu.warn_temp();
+        if
+            matches!(self.mode, Mode::CpuTemperature) ||
+            matches!(self.secondary, Some(Mode::CpuTemperature)) ||
+            self.alarm.uses(Metric::CpuTemperature)
+        {
+            self.sampler.cpu.warn_temp();
         }
-        if matches!(self.mode, Mode::CpuPower) || matches!(self.secondary, Some(Mode::CpuPower)) {
-            self.cpu.warn_rapl();
+        let mut power_metrics = self.selector.power_metrics();
+        if let Some(metric) = self.secondary.as_ref().and_then(Mode::metric).filter(|metric| metric.domain().is_some()) {
+            if !power_metrics.contains(&metric) {
+                power_metrics.push(metric);
+            }
         }
+        if !power_metrics.contains(&Metric::CpuPower) && self.alarm.uses(Metric::CpuPower) {
+            power_metrics.push(Metric::CpuPower);
+        }
+        power_metrics.iter().filter_map(Metric::domain).for_each(|domain| self.sampler.cpu.warn_power(domain));
         if
             matches!(self.mode, Mode::GpuUsage | Mode::GpuTemperature | Mode::GpuPower) ||
-            matches!(self.secondary, Some(Mode::GpuUsage) | Some(Mode::GpuTemperature) | Some(Mode::GpuPower))
+            matches!(self.secondary, Some(Mode::GpuUsage) | Some(Mode::GpuTemperature) | Some(Mode::GpuPower)) ||
+            self.alarm.uses(Metric::GpuTemperature)
         {
-            self.gpu.warn_missing();
+            self.sampler.gpu.warn_missing();
         }
+        self.secondary
+            .iter()
+            .filter_map(Mode::metric)
+            .chain(self.selector.mode_metrics())
+            .for_each(|metric| self.sampler.warn_missing_sensor(metric));
 
         // Data packet
         let mut data: [u8; 64] = [0; 64];
@@ -292,38 +362,50 @@ impl Display {
         data[5] = 1;
 
         // Display loop
+        let mut selector = self.selector.clone();
+        let mut alarm = self.alarm.clone();
         loop {
             // Initialize the packet
             let mut status_data = data.clone();
             let mut matrix = [[false; 14]; 14];
 
-            // Get initial CPU readings & wait
-            let cpu_instant = self.cpu.read_instant();
-            let cpu_energy = self.cpu.read_energy();
-            sleep(self.update);
+            // Get readings & select the display mode
+            let sample = self.sampler.sample(self.update);
+            let mode = selector.select(&sample);
+            let secondary = selector.secondary().or(self.secondary.clone());
+            let alarm_effect = if alarm.update(&sample) { alarm.effect } else { None };
+            let show_values = !(alarm_effect == Some(AlarmEffect::Blink) && alarm.blink());
 
             // Set the pixels and calculate the bytes for the display
-            match &self.secondary {
+            match &secondary {
                 Some(secondary) => {
                     self.insert_data_to_matrix(
                         &mut matrix,
                         1,
-                        self.get_system_info(&self.mode, cpu_instant, cpu_energy)
+                        self.get_system_info(&mode, &sample),
+                        show_values
                     );
                     self.insert_data_to_matrix(
                         &mut matrix,
                         8,
-                        self.get_system_info(secondary, cpu_instant, cpu_energy)
+                        self.get_system_info(secondary, &sample),
+                        show_values
                     );
                 }
                 None => {
                     self.insert_data_to_matrix(
                         &mut matrix,
                         5,
-                        self.get_system_info(&self.mode, cpu_instant, cpu_energy)
+                        self.get_system_info(&mode, &sample),
+                        show_values
                     );
                 }
             }
+            match alarm_effect {
+                Some(AlarmEffect::BlinkAll) if alarm.blink() => matrix = [[false; 14]; 14],
+                Some(AlarmEffect::Invert) => matrix.iter_mut().flatten().for_each(|pixel| *pixel = !*pixel),
+                _ => (),
+            }
             if self.rotate > 0 {
                 dot_matrix::rotate_matrix(&mut matrix, self.rotate);
             }
@@ -338,48 +420,90 @@ impl Display {
         }
     }
 
-    fn get_system_info(&self, mode: &Mode, cpu_instant: CpuInstant, cpu_energy: u64) -> (u16, dot_matrix::Unit) {
+    fn get_system_info(&self, mode: &Mode, sample: &Sample) -> (u16, dot_matrix::Unit) {
         match mode {
             Mode::CpuUsage => (
-                self.cpu.get_usage(cpu_instant) as u16,
+                sample.get(Metric::CpuUsage).round() as u16,
+                dot_matrix::Unit::Percent
+            ),
+            Mode::CpuMaxCoreUsage
+            | Mode::CpuPerformanceUsage
+            | Mode::CpuEfficiencyUsage
+            | Mode::CpuActiveUsage
+            | Mode::CgroupUsage
+            | Mode::RamUsage
+            | Mode::SwapUsage => (
+                sample.get(mode.metric().unwrap()).round() as u16,
                 dot_matrix::Unit::Percent
             ),
-            Mode::CpuTemperature => (
-                self.cpu.get_temp(self.fahrenheit) as u16,
-                if self.fahrenheit { dot_matrix::Unit::Fahrenheit } else { dot_matrix::Unit::Celsius }
+            Mode::RamUsed => (
+                sample.get(Metric::RamUsed).round() as u16,
+                dot_matrix::Unit::Gigabyte
+            ),
+            Mode::Hwmon(symbol) => (
+                sample.get(Metric::Hwmon(symbol.clone())).round() as u16,
+                match hwmon::unit(symbol) {
+                    hwmon::Unit::Temperature if self.sampler.fahrenheit() => dot_matrix::Unit::Fahrenheit,
+                    hwmon::Unit::Temperature => dot_matrix::Unit::Celsius,
+                    _ => dot_matrix::Unit::Watt,
+                }
             ),
-            Mode::CpuPower => (
-                self.cpu.get_power(cpu_energy, self.update.as_millis() as u64),
+            Mode::External => (
+                sample.get(Metric::External).round() as u16,
+                match external::unit() {
+                    external::Unit::Percent => dot_matrix::Unit::Percent,
+                    external::Unit::Celsius if self.sampler.fahrenheit() => dot_matrix::Unit::Fahrenheit,
+                    external::Unit::Celsius => dot_matrix::Unit::Celsius,
+                    external::Unit::Watt => dot_matrix::Unit::Watt,
+                    external::Unit::Gigabyte => dot_matrix::Unit::Gigabyte,
+                    external::Unit::None => dot_matrix::Unit::Empty,
+                }
+            ),
+            Mode::CpuTemperature | Mode::DiskTemperature | Mode::BoardTemperature => (
+                sample.get(mode.metric().unwrap()).round() as u16,
+                if self.sampler.fahrenheit() { dot_matrix::Unit::Fahrenheit } else { dot_matrix::Unit::Celsius }
+            ),
+            Mode::CpuPower | Mode::CpuCorePower | Mode::DramPower | Mode::SystemPower => (
+                sample.get(mode.metric().unwrap()).round() as u16,
                 dot_matrix::Unit::Watt
             ),
             Mode::GpuUsage => (
-                self.gpu.get_usage() as u16,
+                sample.get(Metric::GpuUsage).round() as u16,
                 dot_matrix::Unit::Percent
             ),
             Mode::GpuTemperature => (
-                self.gpu.get_temp(self.fahrenheit) as u16,
-                if self.fahrenheit { dot_matrix::Unit::Fahrenheit } else { dot_matrix::Unit::Celsius }
+                sample.get(Metric::GpuTemperature).round() as u16,
+                if self.sampler.fahrenheit() { dot_matrix::Unit::Fahrenheit } else { dot_matrix::Unit::Celsius }
             ),
             Mode::GpuPower => (
-                self.gpu.get_power(),
+                sample.get(Metric::GpuPower).round() as u16,
                 dot_matrix::Unit::Watt
             ),
             _ => (0, dot_matrix::Unit::Empty),
         }
     }
 
-    fn insert_data_to_matrix(&self, matrix: &mut [[bool; 14]; 14], row_id: usize, data: (u16, dot_matrix::Unit)) {
+    fn insert_data_to_matrix(
+        &self,
+        matrix: &mut [[bool; 14]; 14],
+        row_id: usize,
+        data: (u16, dot_matrix::Unit),
+        show_value: bool,
+    ) {
         let (value, unit) = data;
+        // Values above 999 are displayed as 999
+        let value = value.min(999);
+        let digit = |num: u16| dot_matrix::get_number_pattern(if show_value { num as u8 } else { u8::MAX });
         if value / 100 < 1 {
             // 2-digit number
-            dot_matrix::insert_pattern(matrix, dot_matrix::get_number_pattern((value / 10) as u8), row_id, 1);
-            dot_matrix::insert_pattern(matrix, dot_matrix::get_number_pattern((value % 10) as u8), row_id, 5);
+            dot_matrix::insert_pattern(matrix, digit(value / 10), row_id, 1);
+            dot_matrix::insert_pattern(matrix, digit(value % 10), row_id, 5);
             dot_matrix::insert_pattern(matrix, unit.get_pattern(), 5, 9);
         } else {
             // 3-digit number
-            dot_matrix::insert_pattern(matrix, dot_matrix::get_number_pattern((value / 100) as u8), row_id, 1);
-            dot_matrix::insert_pattern(matrix, dot_matrix::get_number_pattern((value % 100 / 10) as u8), row_id, 5);
-            dot_matrix::insert_pattern(matrix, dot_matrix::get_number_pattern((value % 10) as u8), row_id, 9);
+            dot_matrix::insert_pattern(matrix, digit(value / 100), row_id, 1);
+            dot_matrix::insert_pattern(matrix, digit(value % 100 / 10), row_id, 5);
+            dot_matrix::insert_pattern(matrix, digit(value % 10), row_id, 9);
             dot_matrix::insert_pattern(matrix, unit.get_pattern(), 5, 13);
         }
     }
@@ -7,10 +7,10 @@
 //! - LQ240
 //! - LQ360
 
-use crate::monitor::cpu::Cpu;
-use super::{device_error, Mode};
+use crate::{monitor::sampler::{Metric, Sampler}, utils::config::Config};
+use super::{device_error, layout::{Field, Layout}, Mode};
 use hidapi::HidApi;
-use std::{thread::sleep, time::Duration};
+use std::time::Duration;
 
 pub const DEFAULT_MODE: Mode = Mode::Auto;
 // The temperature limits are hard-coded in the device
@@ -20,27 +20,35 @@ pub const TEMP_LIMIT_C: u8 = 90;
 pub const TEMP_LIMIT_F: u8 = 194;
 
 pub struct Display {
-    cpu: Cpu,
+    sampler: Sampler,
     update: Duration,
-    fahrenheit: bool,
+    layout: Layout,
 }
 
 impl Display {
-    pub fn new(cpu: Cpu, update: Duration, fahrenheit: bool) -> Self {
+    pub fn new(mut sampler: Sampler, update: Duration, config: &Config) -> Self {
+        // Read the sources of the fields
+        let layout = Layout::read(config, &[Field::Power, Field::Temperature, Field::Usage, Field::Frequency]);
+        layout.require(&mut sampler);
+
         Display {
-            cpu,
+            sampler,
             update,
-            fahrenheit,
+            layout,
         }
     }
 
+    /// Returns the metric of the temperature field, which is compared to the limit of the hardware alarm.
+    pub fn alarm_source(&self) -> Metric {
+        self.layout.get(Field::Temperature)
+    }
+
     pub fn run(&self, api: &HidApi, vid: u16, pid: u16) {
         // Connect to device
         let device = api.open(vid, pid).unwrap_or_else(|_| device_error());
 
         // Display warning if a required module is missing
-        self.cpu.warn_temp();
-        self.cpu.warn_rapl();
+        self.layout.warn_missing(&self.sampler);
 
         // Data packet
         let mut data: [u8; 64] = [0; 64];
@@ -57,32 +65,28 @@ impl Display {
             // Initialize the packet
             let mut status_data = data.clone();
 
-            // Read CPU utilization & energy consumption
-            let cpu_instant = self.cpu.read_instant();
-            let cpu_energy = self.cpu.read_energy();
-
-            // Wait
-            sleep(self.update);
+            // Get readings
+            let sample = self.sampler.sample(self.update);
 
             // ----- Write data to the package -----
             // Power consumption
-            let power = (self.cpu.get_power(cpu_energy, self.update.as_millis() as u64)).to_be_bytes();
+            let power = (sample.get(self.layout.get(Field::Power)).round() as u16).to_be_bytes();
             status_data[7] = power[0];
             status_data[8] = power[1];
 
             // Temperature
-            let temp = (self.cpu.get_temp(self.fahrenheit) as f32).to_be_bytes();
-            status_data[9] = if self.fahrenheit { 1 } else { 0 };
+            let temp = sample.get(self.layout.get(Field::Temperature)).to_be_bytes();
+            status_data[9] = if self.sampler.fahrenheit() { 1 } else { 0 };
             status_data[10] = temp[0];
             status_data[11] = temp[1];
             status_data[12] = temp[2];
             status_data[13] = temp[3];
 
             // Utilization
-            status_data[14] = self.cpu.get_usage(cpu_instant);
+            status_data[14] = sample.get(self.layout.get(Field::Usage)).round() as u8;
 
             // Frequency
-            let frequency = (self.cpu.get_frequency()).to_be_bytes();
+            let frequency = (sample.get(self.layout.get(Field::Frequency)).round() as u16).to_be_bytes();
             status_data[15] = frequency[0];
             status_data[16] = frequency[1];
 
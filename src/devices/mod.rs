@@ -1,17 +1,24 @@
 pub mod ag_series;
+pub mod alarm;
 pub mod ak_series;
 pub mod ak400_pro;
 pub mod ak620_pro;
 pub mod ch_series;
 pub mod ch_series_gen2;
 pub mod ch510;
+pub mod layout;
 pub mod ld_series;
 pub mod lp_series;
 pub mod lq_series;
 pub mod ls_series;
+pub mod profiles;
+pub mod rules;
+pub mod status_bar;
 
-use crate::error;
-use std::{process::exit, time::Duration};
+use crate::{error, monitor::{hwmon, sampler::{Metric, Sample}}, utils::config::Config};
+use profiles::Profiles;
+use rules::Rules;
+use std::{process::exit, time::{Duration, Instant}};
 use hidapi::HidDevice;
 
 pub const DEFAULT_VENDOR_ID: u16 = 13875;
@@ -20,36 +27,68 @@ pub const CH510_PRODUCT_ID: u16 = 4352;
 
 pub const AUTO_MODE_INTERVAL: Duration = Duration::from_millis(5000);
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub enum Mode {
     Default,
     Auto,
     CpuTemperature,
     CpuUsage,
     CpuPower,
+    CpuCorePower,
     CpuFrequency,
     CpuFan,
     GpuTemperature,
     GpuUsage,
     GpuPower,
+    DramPower,
+    SystemPower,
+    RamUsage,
+    RamUsed,
+    SwapUsage,
+    DiskTemperature,
+    BoardTemperature,
+    CpuMaxCoreUsage,
+    CpuPerformanceUsage,
+    CpuEfficiencyUsage,
+    CpuActiveUsage,
+    CgroupUsage,
+    /// Sensor selected as `hwmon:<chip>/<input>`
+    Hwmon(String),
+    /// Number read from the source in the `[external]` section
+    External,
     Cpu,
     Gpu,
     Psu,
 }
 
 impl Mode {
-    pub const fn symbol(&self) -> &'static str {
+    pub fn symbol(&self) -> &str {
         match self {
             Mode::Default => "",
             Mode::Auto => "auto",
             Mode::CpuTemperature => "cpu_temp",
             Mode::CpuUsage => "cpu_usage",
             Mode::CpuPower => "cpu_power",
+            Mode::CpuCorePower => "cpu_core_power",
             Mode::CpuFrequency => "cpu_freq",
             Mode::CpuFan => "cpu_fan",
             Mode::GpuTemperature => "gpu_temp",
             Mode::GpuUsage => "gpu_usage",
             Mode::GpuPower => "gpu_power",
+            Mode::DramPower => "dram_power",
+            Mode::SystemPower => "system_power",
+            Mode::RamUsage => "ram_usage",
+            Mode::RamUsed => "ram_used_gb",
+            Mode::SwapUsage => "swap_usage",
+            Mode::DiskTemperature => "disk_temp",
+            Mode::BoardTemperature => "board_temp",
+            Mode::CpuMaxCoreUsage => "cpu_max_core_usage",
+            Mode::CpuPerformanceUsage => "cpu_p_core_usage",
+            Mode::CpuEfficiencyUsage => "cpu_e_core_usage",
+            Mode::CpuActiveUsage => "cpu_active_usage",
+            Mode::CgroupUsage => "cgroup_usage",
+            Mode::Hwmon(symbol) => symbol,
+            Mode::External => "external",
             Mode::Cpu => "cpu",
             Mode::Gpu => "gpu",
             Mode::Psu => "psu",
@@ -62,18 +101,68 @@ impl Mode {
             "cpu_temp" => Some(Self::CpuTemperature),
             "cpu_usage" => Some(Self::CpuUsage),
             "cpu_power" => Some(Self::CpuPower),
+            "cpu_core_power" => Some(Self::CpuCorePower),
             "cpu_freq" => Some(Self::CpuFrequency),
             "cpu_fan" => Some(Self::CpuFan),
             "gpu_temp" => Some(Self::GpuTemperature),
             "gpu_usage" => Some(Self::GpuUsage),
             "gpu_power" => Some(Self::GpuPower),
+            "dram_power" => Some(Self::DramPower),
+            "system_power" => Some(Self::SystemPower),
+            "ram_usage" => Some(Self::RamUsage),
+            "ram_used_gb" => Some(Self::RamUsed),
+            "swap_usage" => Some(Self::SwapUsage),
+            "disk_temp" => Some(Self::DiskTemperature),
+            "board_temp" => Some(Self::BoardTemperature),
+            "cpu_max_core_usage" => Some(Self::CpuMaxCoreUsage),
+            "cpu_p_core_usage" => Some(Self::CpuPerformanceUsage),
+            "cpu_e_core_usage" => Some(Self::CpuEfficiencyUsage),
+            "cpu_active_usage" => Some(Self::CpuActiveUsage),
+            "cgroup_usage" => Some(Self::CgroupUsage),
+            "external" => Some(Self::External),
             "cpu" => Some(Self::Cpu),
             "gpu" => Some(Self::Gpu),
             "psu" => Some(Self::Psu),
+            _ if hwmon::parse(symbol).is_some() => Some(Self::Hwmon(symbol.to_owned())),
             _ => None,
         }
     }
 
+    /// Returns the metric shown by single-value display modes.
+    pub fn metric(&self) -> Option<Metric> {
+        match self {
+            Mode::CpuTemperature => Some(Metric::CpuTemperature),
+            Mode::CpuUsage => Some(Metric::CpuUsage),
+            Mode::CpuPower => Some(Metric::CpuPower),
+            Mode::CpuCorePower => Some(Metric::CpuCorePower),
+            Mode::CpuFrequency => Some(Metric::CpuFrequency),
+            Mode::CpuFan => Some(Metric::CpuFan),
+            Mode::GpuTemperature => Some(Metric::GpuTemperature),
+            Mode::GpuUsage => Some(Metric::GpuUsage),
+            Mode::GpuPower => Some(Metric::GpuPower),
+            Mode::DramPower => Some(Metric::DramPower),
+            Mode::SystemPower => Some(Metric::SystemPower),
+            Mode::RamUsage => Some(Metric::RamUsage),
+            Mode::RamUsed => Some(Metric::RamUsed),
+            Mode::SwapUsage => Some(Metric::SwapUsage),
+            Mode::DiskTemperature => Some(Metric::DiskTemperature),
+            Mode::BoardTemperature => Some(Metric::BoardTemperature),
+            Mode::CpuMaxCoreUsage => Some(Metric::CpuMaxCoreUsage),
+            Mode::CpuPerformanceUsage => Some(Metric::CpuPerformanceUsage),
+            Mode::CpuEfficiencyUsage => Some(Metric::CpuEfficiencyUsage),
+            Mode::CpuActiveUsage => Some(Metric::CpuActiveUsage),
+            Mode::CgroupUsage => Some(Metric::CgroupUsage),
+            Mode::Hwmon(symbol) => Some(Metric::Hwmon(symbol.clone())),
+            Mode::External => Some(Metric::External),
+            _ => None,
+        }
+    }
+
+    /// Checks if the display mode shows a temperature.
+    pub fn is_temperature(&self) -> bool {
+        self.metric().is_some_and(|metric| metric.is_temperature())
+    }
+
     pub fn support_error(&self) -> Mode {
         error!(format!("Display mode \"{}\" is not supported on your device", self.symbol()));
         exit(1);
@@ -85,9 +174,286 @@ impl Mode {
     }
 }
 
+/// Cycles through the display modes of the `auto` mode.
+#[derive(Clone)]
+struct Cycle {
+    modes: Vec<Mode>,
+    index: usize,
+    next_switch: Instant,
+}
+
+impl Cycle {
+    fn new(modes: &[Mode]) -> Self {
+        Cycle {
+            modes: modes.to_vec(),
+            index: 0,
+            next_switch: Instant::now() + AUTO_MODE_INTERVAL,
+        }
+    }
+
+    fn restart(&mut self) {
+        self.index = 0;
+        self.next_switch = Instant::now() + AUTO_MODE_INTERVAL;
+    }
+
+    /// Returns the current display mode, and switches to the next one when its time is over.
+    fn next(&mut self) -> Mode {
+        if Instant::now() >= self.next_switch {
+            self.index = (self.index + 1) % self.modes.len();
+            self.next_switch += AUTO_MODE_INTERVAL;
+        }
+        self.modes[self.index].clone()
+    }
+
+    fn remaining(&self) -> Duration {
+        self.next_switch.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Selects the display mode for each update of the display loop.
+///
+/// The active profile (if any) replaces the configured modes, in `auto` mode it cycles through the
+/// specified modes, then the rules (if any) can override the selection.
+#[derive(Clone)]
+pub struct ModeSelector {
+    mode: Mode,
+    cycle: Cycle,
+    current: Mode,
+    secondary: Option<Mode>,
+    secondary_cycle: Cycle,
+    secondary_current: Option<Mode>,
+    rules: Option<Rules>,
+    profiles: Option<Profiles>,
+}
+
+impl ModeSelector {
+    pub fn new(mode: &Mode, cycle: &[Mode], config: &Config) -> Self {
+        ModeSelector {
+            mode: mode.clone(),
+            cycle: Cycle::new(cycle),
+            current: mode.clone(),
+            secondary: None,
+            secondary_cycle: Cycle::new(&[]),
+            secondary_current: None,
+            rules: Rules::read(config),
+            profiles: Profiles::read(config),
+        }
+    }
+
+    /// Sets the secondary display mode, and the modes of its own `auto` cycle.
+    pub fn with_secondary(mut self, secondary: &Mode, cycle: &[Mode]) -> Self {
+        self.secondary = Some(secondary.clone());
+        self.secondary_cycle = Cycle::new(cycle);
+        self.secondary_current = Some(secondary.clone());
+        self
+    }
+
+    /// Exits the program if the rules or profiles select a display mode that is not supported by the device.
+    pub fn verify(&self, supported: impl Fn(&Mode) -> bool, supported_secondary: impl Fn(&Mode) -> bool) {
+        if let Some(rules) = &self.rules {
+            for mode in rules.modes().filter(|mode| !supported(mode)) {
+                mode.support_error();
+            }
+        }
+        if let Some(profiles) = &self.profiles {
+            for profile in profiles.iter() {
+                if let Some(mode) = profile.mode.as_ref().filter(|mode| !supported(mode)) {
+                    mode.support_error();
+                }
+                if let Some(secondary) = profile.secondary.as_ref().filter(|mode| !supported_secondary(mode)) {
+                    secondary.support_error_secondary();
+                }
+            }
+        }
+    }
+
+    /// Returns all display modes that can be selected (except the secondary modes).
+    pub fn modes(&self) -> Vec<&Mode> {
+        let mut modes = vec![&self.mode];
+        if let Some(rules) = &self.rules {
+            modes.extend(rules.modes());
+        }
+        if let Some(profiles) = &self.profiles {
+            modes.extend(profiles.iter().filter_map(|profile| profile.mode.as_ref()));
+        }
+        if modes.contains(&&Mode::Auto) {
+            modes.extend(&self.cycle.modes);
+        }
+
+        modes
+    }
+
+    /// Returns all secondary display modes that can be selected.
+    pub fn secondary_modes(&self) -> Vec<&Mode> {
+        let mut modes: Vec<&Mode> = self.secondary.iter().collect();
+        if let Some(profiles) = &self.profiles {
+            modes.extend(profiles.iter().filter_map(|profile| profile.secondary.as_ref()));
+        }
+        if modes.contains(&&Mode::Auto) {
+            modes.extend(&self.secondary_cycle.modes);
+        }
+
+        modes
+    }
+
+    /// Returns the metrics that are evaluated by the rules.
+    pub fn metrics(&self) -> Vec<Metric> {
+        match &self.rules {
+            Some(rules) => rules.metrics().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the metrics of all primary and secondary display modes.
+    pub fn mode_metrics(&self) -> Vec<Metric> {
+        let mut metrics = Vec::new();
+        for mode in self.modes().into_iter().chain(self.secondary_modes()) {
+            if let Some(metric) = mode.metric() {
+                if !metrics.contains(&metric) {
+                    metrics.push(metric);
+                }
+            }
+        }
+
+        metrics
+    }
+
+    /// Returns the power metrics of all display modes that are calculated from energy counters.
+    pub fn power_metrics(&self) -> Vec<Metric> {
+        self.mode_metrics().into_iter().filter(|metric| metric.domain().is_some()).collect()
+    }
+
+    /// Returns the length of the next update, shortened to fit the timeframe of the `auto` mode.
+    pub fn update(&self, update: Duration) -> Duration {
+        let mut update = update;
+        if self.current == Mode::Auto {
+            update = update.min(self.cycle.remaining());
+        }
+        if self.secondary_current == Some(Mode::Auto) && !self.secondary_cycle.modes.is_empty() {
+            update = update.min(self.secondary_cycle.remaining());
+        }
+
+        update
+    }
+
+    /// Returns the secondary display mode. The mode of the active profile replaces the configured one.
+    pub fn secondary(&mut self) -> Option<Mode> {
+        let profile_secondary = self.profiles.as_mut().and_then(|profiles| profiles.active()?.secondary.clone());
+        let secondary = profile_secondary.or(self.secondary.clone());
+        if secondary != self.secondary_current {
+            self.secondary_cycle.restart();
+            self.secondary_current = secondary.clone();
+        }
+
+        match secondary {
+            Some(Mode::Auto) if !self.secondary_cycle.modes.is_empty() => Some(self.secondary_cycle.next()),
+            secondary => secondary,
+        }
+    }
+
+    /// Returns the display mode for the sample.
+    pub fn select(&mut self, sample: &Sample) -> Mode {
+        self.select_by(|metric| sample.get(metric))
+    }
+
+    /// Returns the display mode for the values of the metrics evaluated by the rules.
+    fn select_by(&mut self, value: impl Fn(Metric) -> f32) -> Mode {
+        // Apply the mode of the active profile
+        let mode = match self.profiles.as_mut().and_then(|profiles| profiles.active()) {
+            Some(profile) => profile.mode.as_ref().unwrap_or(&self.mode).clone(),
+            None => self.mode.clone(),
+        };
+        if mode != self.current {
+            self.cycle.restart();
+            self.current = mode;
+        }
+
+        let mode = if self.current == Mode::Auto { self.cycle.next() } else { self.current.clone() };
+
+        match &mut self.rules {
+            Some(rules) => rules.select(value).unwrap_or(mode),
+            None => mode,
+        }
+    }
+}
+
 pub fn device_error() -> HidDevice {
     error!("Failed to access the USB device");
     eprintln!("       Try to run the program as root or give permission to the neccesary resources.");
     eprintln!("       You can find instructions about rootless mode on GitHub.");
     exit(1);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        env::temp_dir,
+        fs::{remove_file, write},
+        process,
+    };
+
+    /// Reads the configuration from a temporary file.
+    fn config(name: &str, data: &str) -> Config {
+        let path = temp_dir().join(format!("deepcool-digital-linux-{}-{name}.ini", process::id()));
+        write(&path, data).unwrap();
+        let config = Config::read(path.to_str());
+        let _ = remove_file(path);
+        config
+    }
+
+    #[test]
+    fn cycle_wraps_around() {
+        let mut cycle = Cycle::new(&[Mode::CpuTemperature, Mode::CpuUsage, Mode::GpuTemperature]);
+        assert!(cycle.next() == Mode::CpuTemperature);
+        assert!(cycle.next() == Mode::CpuTemperature);
+
+        for expected in [Mode::CpuUsage, Mode::GpuTemperature, Mode::CpuTemperature] {
+            cycle.next_switch = Instant::now();
+            assert!(cycle.next() == expected);
+        }
+        assert!(cycle.remaining() > Duration::ZERO);
+    }
+
+    #[test]
+    fn cycle_restarts_from_the_first_mode() {
+        let mut cycle = Cycle::new(&[Mode::CpuTemperature, Mode::CpuUsage]);
+        cycle.next_switch = Instant::now();
+        assert!(cycle.next() == Mode::CpuUsage);
+
+        cycle.restart();
+        assert!(cycle.next() == Mode::CpuTemperature);
+    }
+
+    #[test]
+    fn mode_symbols() {
+        assert!(Mode::get("cpu_temp") == Some(Mode::CpuTemperature));
+        assert!(Mode::get("hwmon:d5next/temp1") == Some(Mode::Hwmon("hwmon:d5next/temp1".to_owned())));
+        assert!(Mode::get("hwmon:d5next/temp").is_none());
+        assert!(Mode::get("unknown").is_none());
+        assert_eq!(Mode::Hwmon("hwmon:d5next/temp1".to_owned()).symbol(), "hwmon:d5next/temp1");
+    }
+
+    #[test]
+    fn rules_override_the_auto_cycle() {
+        let config = config("rules", "[rule]\nwhen = cpu_temp > 80\nmode = gpu_temp\nhysteresis = 5\n");
+        let mut selector = ModeSelector::new(&Mode::Auto, &[Mode::CpuTemperature, Mode::CpuUsage], &config);
+        let temp = |temp: f32| move |metric: Metric| if metric == Metric::CpuTemperature { temp } else { 0.0 };
+
+        assert!(selector.select_by(temp(60.0)) == Mode::CpuTemperature);
+        assert!(selector.select_by(temp(85.0)) == Mode::GpuTemperature);
+        assert!(selector.select_by(temp(78.0)) == Mode::GpuTemperature);
+
+        // The cycle keeps running while the rule is active
+        selector.cycle.next_switch = Instant::now();
+        assert!(selector.select_by(temp(70.0)) == Mode::CpuUsage);
+    }
+
+    #[test]
+    fn fixed_mode_without_rules() {
+        let mut selector = ModeSelector::new(&Mode::CpuUsage, &[], &Config::default());
+        assert!(selector.select_by(|_| 100.0) == Mode::CpuUsage);
+        assert!(selector.metrics().is_empty());
+        assert!(selector.update(Duration::from_millis(1000)) == Duration::from_millis(1000));
+    }
+}
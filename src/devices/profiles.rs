@@ -0,0 +1,121 @@
+//! Switches the display modes while a specific process is running.
+//!
+//! Profiles are defined in the configuration file, the first one with a running process is applied:
+//! ```ini
+//! [profile]
+//! name = blender
+//! process = blender
+//! cmdline = --render
+//! mode = gpu_usage
+//! secondary = gpu_temp
+//! ```
+
+use crate::{monitor::process::{get_process_list, Process}, utils::config::{Config, Section}};
+use super::Mode;
+use colored::*;
+use std::time::{Duration, Instant};
+
+/// The process list is scanned at this interval instead of every update.
+pub const PROCESS_SCAN_INTERVAL: Duration = Duration::from_millis(2000);
+
+#[derive(Clone)]
+pub struct Profile {
+    name: String,
+    process: Option<String>,
+    cmdline: Option<String>,
+    pub mode: Option<Mode>,
+    pub secondary: Option<Mode>,
+}
+
+impl Profile {
+    fn read(section: &Section) -> Self {
+        let name = section.get("name").unwrap_or_else(|| section.missing_error("name"));
+        let process = section.get("process").map(|p| p.to_owned());
+        let cmdline = section.get("cmdline").map(|c| c.to_owned());
+        if process.is_none() && cmdline.is_none() {
+            section.missing_error::<()>("process\" or \"cmdline");
+        }
+
+        let read_mode = |key: &str| {
+            section.get(key).map(|symbol| Mode::get(symbol).unwrap_or_else(|| section.value_error(key, symbol)))
+        };
+        let mode = read_mode("mode");
+        let secondary = read_mode("secondary");
+        if mode.is_none() && secondary.is_none() {
+            section.missing_error::<()>("mode\" or \"secondary");
+        }
+
+        Profile {
+            name: name.to_owned(),
+            process,
+            cmdline,
+            mode,
+            secondary,
+        }
+    }
+
+    /// Checks if the process belongs to the profile.
+    fn matches(&self, process: &Process) -> bool {
+        let name_matches = match &self.process {
+            Some(name) => &process.name == name || process.executable() == name,
+            None => true,
+        };
+        let cmdline_matches = match &self.cmdline {
+            Some(cmdline) => process.cmdline.contains(cmdline.as_str()),
+            None => true,
+        };
+
+        name_matches && cmdline_matches
+    }
+}
+
+#[derive(Clone)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    active: Option<usize>,
+    last_scan: Option<Instant>,
+}
+
+impl Profiles {
+    /// Reads all `[profile]` sections of the configuration.
+    pub fn read(config: &Config) -> Option<Self> {
+        let profiles: Vec<Profile> = config.sections("profile").map(Profile::read).collect();
+        if profiles.is_empty() {
+            return None;
+        }
+
+        Some(Profiles {
+            profiles,
+            active: None,
+            last_scan: None,
+        })
+    }
+
+    /// Returns all profiles in the order of priority.
+    pub fn iter(&self) -> impl Iterator<Item = &Profile> {
+        self.profiles.iter()
+    }
+
+    /// Returns the active profile. The running processes are scanned periodically.
+    pub fn active(&mut self) -> Option<&Profile> {
+        let scan = match self.last_scan {
+            Some(last_scan) => last_scan.elapsed() >= PROCESS_SCAN_INTERVAL,
+            None => true,
+        };
+        if scan {
+            self.last_scan = Some(Instant::now());
+
+            let processes = get_process_list();
+            let active = self.profiles.iter().position(|profile| processes.iter().any(|p| profile.matches(p)));
+            if active != self.active {
+                match active {
+                    Some(id) => println!("Profile activated: {}", self.profiles[id].name.bright_cyan()),
+                    None => println!("Profile deactivated: {}", self.profiles[self.active.unwrap()].name.bright_cyan()),
+                }
+                self.active = active;
+            }
+        }
+
+        self.active.map(|id| &self.profiles[id])
+    }
+}
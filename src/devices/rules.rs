@@ -0,0 +1,224 @@
+//! Selects the display mode based on the current system state.
+//!
+//! Rules are defined in the configuration file and evaluated in the order of appearance:
+//! ```ini
+//! [rule]
+//! when = cpu_temp > 80
+//! mode = cpu_temp
+//! hysteresis = 5
+//! hold = 3000
+//! ```
+
+use crate::{monitor::sampler::Metric, utils::config::{Config, Section}};
+use super::Mode;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    fn get(symbol: &str) -> Option<Comparison> {
+        match symbol {
+            ">" => Some(Self::Greater),
+            ">=" => Some(Self::GreaterOrEqual),
+            "<" => Some(Self::Less),
+            "<=" => Some(Self::LessOrEqual),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Rule {
+    metric: Metric,
+    comparison: Comparison,
+    threshold: f32,
+    hysteresis: f32,
+    hold: Duration,
+    mode: Mode,
+}
+
+impl Rule {
+    fn read(section: &Section) -> Self {
+        // Parse the condition: <metric> <comparison> <threshold>
+        let condition = section.get("when").unwrap_or_else(|| section.missing_error("when"));
+        let mut parts = condition.split_whitespace();
+        let metric = parts.next().and_then(Metric::get);
+        let comparison = parts.next().and_then(Comparison::get);
+        let threshold = parts.next().and_then(|t| t.parse::<f32>().ok());
+        let (metric, comparison, threshold) = match (metric, comparison, threshold, parts.next()) {
+            (Some(metric), Some(comparison), Some(threshold), None) => (metric, comparison, threshold),
+            _ => section.value_error("when", condition),
+        };
+
+        let mode = section.get("mode").unwrap_or_else(|| section.missing_error("mode"));
+        let mode = match Mode::get(mode) {
+            Some(Mode::Auto) | None => section.value_error("mode", mode),
+            Some(mode) => mode,
+        };
+
+        Rule {
+            metric,
+            comparison,
+            threshold,
+            hysteresis: section.parse::<f32>("hysteresis").unwrap_or(0.0).abs(),
+            hold: Duration::from_millis(section.parse::<u64>("hold").unwrap_or(0)),
+            mode,
+        }
+    }
+
+    /// Checks the condition of the rule. Active rules stay active until the value crosses the hysteresis band.
+    fn matches(&self, value: f32, active: bool) -> bool {
+        let hysteresis = if active { self.hysteresis } else { 0.0 };
+        match self.comparison {
+            Comparison::Greater => value > self.threshold - hysteresis,
+            Comparison::GreaterOrEqual => value >= self.threshold - hysteresis,
+            Comparison::Less => value < self.threshold + hysteresis,
+            Comparison::LessOrEqual => value <= self.threshold + hysteresis,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Rules {
+    rules: Vec<Rule>,
+    active: Option<usize>,
+    since: Instant,
+}
+
+impl Rules {
+    /// Reads all `[rule]` sections of the configuration.
+    pub fn read(config: &Config) -> Option<Self> {
+        let rules: Vec<Rule> = config.sections("rule").map(Rule::read).collect();
+        if rules.is_empty() {
+            return None;
+        }
+
+        Some(Rules {
+            rules,
+            active: None,
+            since: Instant::now(),
+        })
+    }
+
+    /// Returns the display modes that can be selected by the rules.
+    pub fn modes(&self) -> impl Iterator<Item = &Mode> {
+        self.rules.iter().map(|rule| &rule.mode)
+    }
+
+    /// Returns the metrics that are evaluated by the rules.
+    pub fn metrics(&self) -> impl Iterator<Item = Metric> + '_ {
+        self.rules.iter().map(|rule| rule.metric.clone())
+    }
+
+    /// Evaluates the rules with the values of the metrics and returns the mode of the first matching one.
+    pub fn select(&mut self, value: impl Fn(Metric) -> f32) -> Option<Mode> {
+        // Keep the current mode until the hold time is over
+        if let Some(id) = self.active {
+            if self.since.elapsed() < self.rules[id].hold {
+                return Some(self.rules[id].mode.clone());
+            }
+        }
+
+        for (id, rule) in self.rules.iter().enumerate() {
+            if rule.matches(value(rule.metric.clone()), self.active == Some(id)) {
+                if self.active != Some(id) {
+                    self.active = Some(id);
+                    self.since = Instant::now();
+                }
+                return Some(rule.mode.clone());
+            }
+        }
+        self.active = None;
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(comparison: Comparison, threshold: f32, hysteresis: f32, hold: u64, mode: Mode) -> Rule {
+        Rule {
+            metric: Metric::CpuTemperature,
+            comparison,
+            threshold,
+            hysteresis,
+            hold: Duration::from_millis(hold),
+            mode,
+        }
+    }
+
+    fn rules(rules: Vec<Rule>) -> Rules {
+        Rules {
+            rules,
+            active: None,
+            since: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(rule(Comparison::Greater, 80.0, 0.0, 0, Mode::Auto).matches(81.0, false));
+        assert!(!rule(Comparison::Greater, 80.0, 0.0, 0, Mode::Auto).matches(80.0, false));
+        assert!(rule(Comparison::GreaterOrEqual, 80.0, 0.0, 0, Mode::Auto).matches(80.0, false));
+        assert!(rule(Comparison::Less, 20.0, 0.0, 0, Mode::Auto).matches(19.0, false));
+        assert!(!rule(Comparison::Less, 20.0, 0.0, 0, Mode::Auto).matches(20.0, false));
+        assert!(rule(Comparison::LessOrEqual, 20.0, 0.0, 0, Mode::Auto).matches(20.0, false));
+    }
+
+    #[test]
+    fn hysteresis_only_applies_to_active_rules() {
+        let greater = rule(Comparison::Greater, 80.0, 5.0, 0, Mode::Auto);
+        assert!(!greater.matches(78.0, false));
+        assert!(greater.matches(78.0, true));
+        assert!(!greater.matches(75.0, true));
+
+        let less = rule(Comparison::Less, 20.0, 5.0, 0, Mode::Auto);
+        assert!(!less.matches(22.0, false));
+        assert!(less.matches(22.0, true));
+        assert!(!less.matches(25.0, true));
+    }
+
+    #[test]
+    fn first_matching_rule_is_selected() {
+        let mut rules = rules(vec![
+            rule(Comparison::Greater, 90.0, 0.0, 0, Mode::CpuTemperature),
+            rule(Comparison::Greater, 50.0, 0.0, 0, Mode::CpuUsage),
+        ]);
+        assert!(rules.select(|_| 95.0) == Some(Mode::CpuTemperature));
+        assert!(rules.select(|_| 60.0) == Some(Mode::CpuUsage));
+        assert!(rules.select(|_| 40.0).is_none());
+        assert!(rules.active.is_none());
+    }
+
+    #[test]
+    fn active_rule_stays_within_hysteresis() {
+        let mut rules = rules(vec![rule(Comparison::Greater, 80.0, 5.0, 0, Mode::CpuTemperature)]);
+        assert!(rules.select(|_| 78.0).is_none());
+        assert!(rules.select(|_| 81.0) == Some(Mode::CpuTemperature));
+        assert!(rules.select(|_| 78.0) == Some(Mode::CpuTemperature));
+        assert!(rules.select(|_| 75.0).is_none());
+    }
+
+    #[test]
+    fn active_rule_is_held() {
+        let mut rules = rules(vec![
+            rule(Comparison::Greater, 80.0, 0.0, 3000, Mode::CpuTemperature),
+            rule(Comparison::Greater, 0.0, 0.0, 0, Mode::CpuUsage),
+        ]);
+        assert!(rules.select(|_| 85.0) == Some(Mode::CpuTemperature));
+        // The condition no longer matches, but the hold time is not over
+        assert!(rules.select(|_| 50.0) == Some(Mode::CpuTemperature));
+
+        rules.since -= Duration::from_millis(3000);
+        assert!(rules.select(|_| 50.0) == Some(Mode::CpuUsage));
+        assert!(rules.active == Some(1));
+    }
+}
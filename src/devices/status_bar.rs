@@ -0,0 +1,120 @@
+//! Calculates the segments of the 10-segment status bar.
+//!
+//! The source of the bar can be changed in the configuration file:
+//! ```ini
+//! [status_bar]
+//! source = cpu_temp
+//! min = 30
+//! max = 90
+//! curve = linear
+//! ```
+//! Devices with a second bar use the `[secondary_status_bar]` section.
+
+use crate::{monitor::sampler::{Metric, Sample}, utils::config::Config};
+
+/// Shapes the distribution of the segments.
+#[derive(Clone, Copy)]
+enum Curve {
+    Linear,
+    /// Fills slowly at first, useful for highlighting high values.
+    Quadratic,
+    /// Fills quickly at first, useful for highlighting low values.
+    Sqrt,
+}
+
+impl Curve {
+    fn get(symbol: &str) -> Option<Curve> {
+        match symbol {
+            "linear" => Some(Self::Linear),
+            "quadratic" => Some(Self::Quadratic),
+            "sqrt" => Some(Self::Sqrt),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, ratio: f32) -> f32 {
+        match self {
+            Curve::Linear => ratio,
+            Curve::Quadratic => ratio * ratio,
+            Curve::Sqrt => ratio.sqrt(),
+        }
+    }
+}
+
+pub struct StatusBar {
+    pub metric: Metric,
+    min: f32,
+    max: f32,
+    curve: Curve,
+}
+
+impl StatusBar {
+    /// Reads the section of the status bar, the default metric is displayed in the `0-100` range.
+    pub fn read(config: &Config, name: &str, default: Metric, fahrenheit: bool) -> Self {
+        let section = match config.sections(name).last() {
+            Some(section) => section,
+            None => return StatusBar { metric: default, min: 0.0, max: 100.0, curve: Curve::Linear },
+        };
+
+        let metric = match section.get("source") {
+            Some(symbol) => match Metric::get(symbol) {
+                Some(
+                    metric @ (Metric::CpuTemperature
+                    | Metric::CpuUsage
+                    | Metric::CpuMaxCoreUsage
+                    | Metric::CpuPerformanceUsage
+                    | Metric::CpuEfficiencyUsage
+                    | Metric::CpuActiveUsage
+                    | Metric::CgroupUsage
+                    | Metric::CpuPower
+                    | Metric::GpuTemperature
+                    | Metric::GpuUsage
+                    | Metric::GpuPower
+                    | Metric::RamUsage
+                    | Metric::SwapUsage
+                    | Metric::DiskTemperature
+                    | Metric::BoardTemperature
+                    | Metric::Hwmon(_)
+                    | Metric::External),
+                ) => metric,
+                _ => section.value_error("source", symbol),
+            },
+            None => default,
+        };
+
+        // Temperatures are scaled in the unit of the display, power is scaled up to the TDP
+        let (min, max) = match metric {
+            _ if metric.is_temperature() && fahrenheit => (86.0, 194.0),
+            _ if metric.is_temperature() => (30.0, 90.0),
+            Metric::CpuPower | Metric::GpuPower => match section.parse::<f32>("tdp") {
+                Some(tdp) => (0.0, tdp),
+                None => section.missing_error("tdp"),
+            },
+            _ => (0.0, 100.0),
+        };
+        let min = section.parse::<f32>("min").unwrap_or(min);
+        let max = section.parse::<f32>("max").unwrap_or(max);
+        if max <= min {
+            section.value_error::<()>("max", &max.to_string());
+        }
+
+        let curve = match section.get("curve") {
+            Some(symbol) => Curve::get(symbol).unwrap_or_else(|| section.value_error("curve", symbol)),
+            None => Curve::Linear,
+        };
+
+        StatusBar { metric, min, max, curve }
+    }
+
+    /// Returns the number of lit segments. At least 1 segment is always lit.
+    pub fn segments(&self, sample: &Sample) -> u8 {
+        let ratio = ((sample.get(self.metric.clone()) - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+
+        ((self.curve.apply(ratio) * 10.0).round() as u8).max(1)
+    }
+
+    /// Checks if the bar displays a GPU metric.
+    pub fn is_gpu(&self) -> bool {
+        matches!(self.metric, Metric::GpuTemperature | Metric::GpuUsage | Metric::GpuPower)
+    }
+}
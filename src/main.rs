@@ -5,13 +5,13 @@ mod utils;
 use colored::*;
 use devices::*;
 use hidapi::HidApi;
-use monitor::{cpu, gpu};
+use monitor::{coolant, cpu, fan, gpu, psu, sampler::Sampler};
 use std::process::exit;
-use utils::{args::Args, status::*};
+use utils::{args::Args, config::Config, status::*};
 
 /// Common warning checks for command arguments.
 mod common_warnings {
-    use crate::{devices::Mode, utils::args::Args, warning};
+    use crate::{devices::Mode, utils::{args::Args, config::Config}, warning};
 
     pub fn mode_change(args: &Args) {
         if args.mode != Mode::Default {
@@ -31,12 +31,6 @@ mod common_warnings {
         }
     }
 
-    pub fn alarm(args: &Args) {
-        if args.alarm {
-            warning!("Alarm is not supported, value will be ignored");
-        }
-    }
-
     pub fn alarm_hardcoded(args: &Args) {
         if args.alarm {
             warning!("The alarm is hard-coded in your device, value will be ignored");
@@ -54,11 +48,18 @@ mod common_warnings {
             warning!("Displaying leading zeros is not supported, value will be ignored");
         }
     }
+
+    pub fn mode_selection(config: &Config) {
+        if config.sections("rule").next().is_some() || config.sections("profile").next().is_some() {
+            warning!("Display mode rules and profiles are not supported, they will be ignored");
+        }
+    }
 }
 
 fn main() {
     // Read args
     let args = Args::read();
+    let config = Config::read(args.config.as_deref());
     println!("--- Deepcool Digital Linux ---");
 
     // Find dedicated or integrated GPU
@@ -135,17 +136,26 @@ fn main() {
         exit(1);
     }
 
-    // Initialize CPU & GPU monitoring
-    let cpu = cpu::Cpu::new();
+    // Initialize CPU, GPU, fan, PSU & coolant monitoring
+    let cpu = cpu::Cpu::new(args.cpu_sensor.as_deref(), &config);
+    match (cpu.temp_sensor_name(), cpu.temp_calibration().describe()) {
+        (Some(sensor), Some(calibration)) => println!("CPU sensor: {} [{calibration}]", sensor.bright_green()),
+        (Some(sensor), None) => println!("CPU sensor: {}", sensor.bright_green()),
+        (None, _) => println!("CPU sensor: {}", "none".bright_black()),
+    }
     let gpu = gpu::Gpu::new(pci_device);
+    let fan = fan::Fan::new(&config);
+    let psu = psu::Psu::new(&config);
+    let coolant = coolant::Coolant::new(&config);
+    let sampler = Sampler::new(cpu, gpu, fan, psu, coolant, args.fahrenheit, &config);
 
     // Connect to device and send datastream
     match product_id {
         // AK Series
         1..=4 => {
-            println!("Supported modes: {} [default: {}]", "auto cpu_temp cpu_usage".bold(), ak_series::DEFAULT_MODE.symbol());
+            println!("Supported modes: {} [default: {}]", "auto cpu_temp cpu_usage gpu_temp gpu_usage cpu_max_core_usage cpu_p_core_usage cpu_e_core_usage cpu_active_usage cgroup_usage ram_usage swap_usage disk_temp board_temp hwmon:<chip>/<input> external".bold(), ak_series::DEFAULT_MODE.symbol());
             // Connect to device
-            let ak_device = ak_series::Display::new(cpu, &args.mode, args.update, args.fahrenheit, args.alarm);
+            let ak_device = ak_series::Display::new(sampler, &args.mode, args.update, args.alarm, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ak_device.mode,
@@ -154,12 +164,11 @@ fn main() {
                 None,
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
                 Alarm {
-                    state: if args.alarm { AlarmState::On } else { AlarmState::Off },
-                    temp_limit: if args.fahrenheit {
-                        ak_series::TEMP_LIMIT_F
-                    } else {
-                        ak_series::TEMP_LIMIT_C
-                    },
+                    state: if ak_device.alarm.enabled { AlarmState::On } else { AlarmState::Off },
+                    source: ak_device.alarm.source.symbol().to_owned(),
+                    limit: ak_device.alarm.limit,
+                    hysteresis: ak_device.alarm.hysteresis,
+                    effect: None,
                     temp_warning: 0,
                 },
                 args.update,
@@ -172,9 +181,9 @@ fn main() {
         }
         // LS Series
         6 => {
-            println!("Supported modes: {} [default: {}]", "auto cpu_temp cpu_power".bold(), ls_series::DEFAULT_MODE.symbol());
+            println!("Supported modes: {} [default: {}]", "auto cpu_temp cpu_power cpu_core_power gpu_temp gpu_power dram_power system_power disk_temp board_temp hwmon:<chip>/<input> external".bold(), ls_series::DEFAULT_MODE.symbol());
             // Connect to device
-            let ls_device = ls_series::Display::new(cpu, &args.mode, args.update, args.fahrenheit, args.alarm);
+            let ls_device = ls_series::Display::new(sampler, &args.mode, args.update, args.alarm, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ls_device.mode,
@@ -183,12 +192,11 @@ fn main() {
                 None,
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
                 Alarm {
-                    state: if args.alarm { AlarmState::On } else { AlarmState::Off },
-                    temp_limit: if args.fahrenheit {
-                        ls_series::TEMP_LIMIT_F
-                    } else {
-                        ls_series::TEMP_LIMIT_C
-                    },
+                    state: if ls_device.alarm.enabled { AlarmState::On } else { AlarmState::Off },
+                    source: ls_device.alarm.source.symbol().to_owned(),
+                    limit: ls_device.alarm.limit,
+                    hysteresis: ls_device.alarm.hysteresis,
+                    effect: None,
                     temp_warning: 0,
                 },
                 args.update,
@@ -201,9 +209,9 @@ fn main() {
         }
         // AG Series
         8 => {
-            println!("Supported modes: {} [default: {}]", "auto cpu_temp cpu_usage".bold(), ag_series::DEFAULT_MODE.symbol());
+            println!("Supported modes: {} [default: {}]", "auto cpu_temp cpu_usage gpu_temp gpu_usage cpu_max_core_usage cpu_p_core_usage cpu_e_core_usage cpu_active_usage cgroup_usage ram_usage swap_usage disk_temp board_temp hwmon:<chip>/<input> external".bold(), ag_series::DEFAULT_MODE.symbol());
             // Connect to device
-            let ag_device = ag_series::Display::new(cpu, &args.mode, args.update, args.alarm);
+            let ag_device = ag_series::Display::new(sampler, &args.mode, args.update, args.alarm, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ag_device.mode,
@@ -212,8 +220,11 @@ fn main() {
                 None,
                 TemperatureUnit::Celsius,
                 Alarm {
-                    state: if args.alarm { AlarmState::On } else { AlarmState::Off },
-                    temp_limit: ag_series::TEMP_LIMIT_C,
+                    state: if ag_device.alarm.enabled { AlarmState::On } else { AlarmState::Off },
+                    source: ag_device.alarm.source.symbol().to_owned(),
+                    limit: ag_device.alarm.limit,
+                    hysteresis: ag_device.alarm.hysteresis,
+                    effect: None,
                     temp_warning: 0,
                 },
                 args.update,
@@ -229,7 +240,7 @@ fn main() {
         10 => {
             println!("Supported modes: {}", "auto".bold());
             // Connect to device
-            let ld_device = ld_series::Display::new(cpu, args.update, args.fahrenheit, args.lead_zeros);
+            let ld_device = ld_series::Display::new(sampler, args.update, args.lead_zeros, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ld_series::DEFAULT_MODE,
@@ -239,16 +250,20 @@ fn main() {
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
                 Alarm {
                     state: AlarmState::Auto,
-                    temp_limit: if args.fahrenheit {
-                        ld_series::TEMP_LIMIT_F
+                    source: ld_device.alarm_source().symbol().to_owned(),
+                    limit: if args.fahrenheit {
+                        ld_series::TEMP_LIMIT_F.into()
                     } else {
-                        ld_series::TEMP_LIMIT_C
+                        ld_series::TEMP_LIMIT_C.into()
                     },
+                    hysteresis: 0,
+                    effect: None,
                     temp_warning: 0,
                 },
                 args.update,
             );
             common_warnings::mode_change(&args);
+            common_warnings::mode_selection(&config);
             common_warnings::secondary_mode(&args);
             common_warnings::alarm_hardcoded(&args);
             common_warnings::rotate(&args);
@@ -259,15 +274,23 @@ fn main() {
         12 => {
             println!(
                 "Supported modes: {} [default: {}]",
-                "cpu_usage cpu_temp cpu_power gpu_usage gpu_temp gpu_power".bold(),
+                "cpu_usage cpu_temp cpu_power cpu_core_power gpu_usage gpu_temp gpu_power dram_power system_power cpu_max_core_usage cpu_p_core_usage cpu_e_core_usage cpu_active_usage cgroup_usage ram_usage ram_used_gb swap_usage disk_temp board_temp hwmon:<chip>/<input> external".bold(),
                 lp_series::DEFAULT_MODE.symbol()
             );
             println!(
                 "Supported secondary: {}",
-                "cpu_usage cpu_temp cpu_power gpu_usage gpu_temp gpu_power".bold()
+                "cpu_usage cpu_temp cpu_power cpu_core_power gpu_usage gpu_temp gpu_power dram_power system_power cpu_max_core_usage cpu_p_core_usage cpu_e_core_usage cpu_active_usage cgroup_usage ram_usage ram_used_gb swap_usage disk_temp board_temp hwmon:<chip>/<input> external".bold()
             );
             // Connect to device
-            let lp_device = lp_series::Display::new(cpu, gpu, &args.mode, &args.secondary, args.update, args.fahrenheit, args.rotate);
+            let lp_device = lp_series::Display::new(
+                sampler,
+                &args.mode,
+                &args.secondary,
+                args.update,
+                args.rotate,
+                args.alarm,
+                &config,
+            );
             // Print current configuration & warnings
             print_device_status(
                 &lp_device.mode,
@@ -275,10 +298,16 @@ fn main() {
                 Some(args.rotate),
                 None,
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
-                Alarm { state: AlarmState::NotSupported, temp_limit: 0, temp_warning: 0 },
+                Alarm {
+                    state: if lp_device.alarm.enabled { AlarmState::On } else { AlarmState::Off },
+                    source: lp_device.alarm.source.symbol().to_owned(),
+                    limit: lp_device.alarm.limit,
+                    hysteresis: lp_device.alarm.hysteresis,
+                    effect: lp_device.alarm.effect,
+                    temp_warning: 0,
+                },
                 args.update,
             );
-            common_warnings::alarm(&args);
             common_warnings::lead_zeros(&args);
             // Display loop
             lp_device.run(&api, DEFAULT_VENDOR_ID, product_id);
@@ -287,7 +316,7 @@ fn main() {
         13 | 15 | 31 | 41 | 42 | 43 | 44 => {
             println!("Supported modes: {}", "auto".bold());
             // Connect to device
-            let lq_device = devices::lq_series::Display::new(cpu, args.update, args.fahrenheit);
+            let lq_device = devices::lq_series::Display::new(sampler, args.update, &config);
             // Print current configuration & warnings
             print_device_status(
                 &lq_series::DEFAULT_MODE,
@@ -297,11 +326,14 @@ fn main() {
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
                 Alarm {
                     state: AlarmState::Auto,
-                    temp_limit: if args.fahrenheit {
-                        lq_series::TEMP_LIMIT_F
+                    source: lq_device.alarm_source().symbol().to_owned(),
+                    limit: if args.fahrenheit {
+                        lq_series::TEMP_LIMIT_F.into()
                     } else {
-                        lq_series::TEMP_LIMIT_C
+                        lq_series::TEMP_LIMIT_C.into()
                     },
+                    hysteresis: 0,
+                    effect: None,
                     temp_warning: if args.fahrenheit {
                         lq_series::TEMP_WARNING_F
                     } else {
@@ -311,6 +343,7 @@ fn main() {
                 args.update,
             );
             common_warnings::mode_change(&args);
+            common_warnings::mode_selection(&config);
             common_warnings::secondary_mode(&args);
             common_warnings::alarm_hardcoded(&args);
             common_warnings::rotate(&args);
@@ -322,7 +355,7 @@ fn main() {
         16 => {
             println!("Supported modes: {}", "auto".bold());
             // Connect to device
-            let ak400_pro = devices::ak400_pro::Display::new(cpu, args.update, args.fahrenheit);
+            let ak400_pro = devices::ak400_pro::Display::new(sampler, args.update, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ak400_pro::DEFAULT_MODE,
@@ -332,11 +365,14 @@ fn main() {
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
                 Alarm {
                     state: AlarmState::Auto,
-                    temp_limit: if args.fahrenheit {
-                        ak400_pro::TEMP_LIMIT_F
+                    source: ak400_pro.alarm_source().symbol().to_owned(),
+                    limit: if args.fahrenheit {
+                        ak400_pro::TEMP_LIMIT_F.into()
                     } else {
-                        ak400_pro::TEMP_LIMIT_C
+                        ak400_pro::TEMP_LIMIT_C.into()
                     },
+                    hysteresis: 0,
+                    effect: None,
                     temp_warning: if args.fahrenheit {
                         ak400_pro::TEMP_WARNING_F
                     } else {
@@ -346,6 +382,7 @@ fn main() {
                 args.update,
             );
             common_warnings::mode_change(&args);
+            common_warnings::mode_selection(&config);
             common_warnings::secondary_mode(&args);
             common_warnings::alarm_hardcoded(&args);
             common_warnings::rotate(&args);
@@ -357,7 +394,7 @@ fn main() {
         17 | 18 => {
             println!("Supported modes: {}", "auto".bold());
             // Connect to device
-            let ak620_pro = devices::ak620_pro::Display::new(cpu, args.update, args.fahrenheit);
+            let ak620_pro = devices::ak620_pro::Display::new(sampler, args.update, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ak620_pro::DEFAULT_MODE,
@@ -367,11 +404,14 @@ fn main() {
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
                 Alarm {
                     state: AlarmState::Auto,
-                    temp_limit: if args.fahrenheit {
-                        ak620_pro::TEMP_LIMIT_F
+                    source: ak620_pro.alarm_source().symbol().to_owned(),
+                    limit: if args.fahrenheit {
+                        ak620_pro::TEMP_LIMIT_F.into()
                     } else {
-                        ak620_pro::TEMP_LIMIT_C
+                        ak620_pro::TEMP_LIMIT_C.into()
                     },
+                    hysteresis: 0,
+                    effect: None,
                     temp_warning: if args.fahrenheit {
                         ak620_pro::TEMP_WARNING_F
                     } else {
@@ -381,6 +421,7 @@ fn main() {
                 args.update,
             );
             common_warnings::mode_change(&args);
+            common_warnings::mode_selection(&config);
             common_warnings::secondary_mode(&args);
             common_warnings::alarm_hardcoded(&args);
             common_warnings::rotate(&args);
@@ -391,15 +432,12 @@ fn main() {
         // CH170 | CH270 | CH690
         19 | 22 | 27 => {
             println!(
-                "Supported modes: {} {} {} {} [default: {}]",
-                "auto cpu_freq".bold(),
-                "cpu_fan".bright_black().strikethrough(),
-                "gpu".bold(),
-                "psu".bright_black().strikethrough(),
+                "Supported modes: {} [default: {}]",
+                "auto cpu_freq cpu_fan gpu psu".bold(),
                 ch_series_gen2::DEFAULT_MODE.symbol()
             );
             // Connect to device
-            let ch_gen2_device = ch_series_gen2::Display::new(cpu, gpu, &args.mode, args.update, args.fahrenheit);
+            let ch_gen2_device = ch_series_gen2::Display::new(sampler, &args.mode, args.update, args.alarm, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ch_gen2_device.mode,
@@ -407,11 +445,17 @@ fn main() {
                 None,
                 None,
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
-                Alarm { state: AlarmState::NotSupported, temp_limit: 0, temp_warning: 0 },
+                Alarm {
+                    state: if ch_gen2_device.alarm.enabled { AlarmState::On } else { AlarmState::Off },
+                    source: ch_gen2_device.alarm.source.symbol().to_owned(),
+                    limit: ch_gen2_device.alarm.limit,
+                    hysteresis: ch_gen2_device.alarm.hysteresis,
+                    effect: ch_gen2_device.alarm.effect,
+                    temp_warning: 0,
+                },
                 args.update,
             );
             common_warnings::secondary_mode(&args);
-            common_warnings::alarm(&args);
             common_warnings::rotate(&args);
             common_warnings::lead_zeros(&args);
             // Display loop
@@ -419,10 +463,14 @@ fn main() {
         }
         // CH Series & MORPHEUS
         5 | 7 | 21 => {
-            println!("Supported modes: {} [default: {}]", "auto cpu_temp cpu_usage".bold(), ch_series::DEFAULT_MODE.symbol());
-            println!("Supported secondary: {}", "gpu_temp gpu_usage".bold());
+            println!(
+                "Supported modes: {} [default: {}]",
+                "auto cpu_temp cpu_usage gpu_temp gpu_usage ram_usage swap_usage cpu_max_core_usage cpu_p_core_usage cpu_e_core_usage cpu_active_usage cgroup_usage disk_temp board_temp hwmon:<chip>/<input> external".bold(),
+                ch_series::DEFAULT_MODE.symbol()
+            );
+            println!("Supported secondary: {}", "auto cpu_temp cpu_usage gpu_temp gpu_usage ram_usage swap_usage cpu_max_core_usage cpu_p_core_usage cpu_e_core_usage cpu_active_usage cgroup_usage disk_temp board_temp hwmon:<chip>/<input> external".bold());
             // Connect to device
-            let ch_device = ch_series::Display::new(cpu, gpu, &args.mode, &args.secondary, args.update, args.fahrenheit);
+            let ch_device = ch_series::Display::new(sampler, &args.mode, &args.secondary, args.update, args.alarm, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ch_device.mode,
@@ -430,10 +478,16 @@ fn main() {
                 None,
                 None,
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
-                Alarm { state: AlarmState::NotSupported, temp_limit: 0, temp_warning: 0 },
+                Alarm {
+                    state: if ch_device.alarm.enabled { AlarmState::On } else { AlarmState::Off },
+                    source: ch_device.alarm.source.symbol().to_owned(),
+                    limit: ch_device.alarm.limit,
+                    hysteresis: ch_device.alarm.hysteresis,
+                    effect: ch_device.alarm.effect,
+                    temp_warning: 0,
+                },
                 args.update,
             );
-            common_warnings::alarm(&args);
             common_warnings::rotate(&args);
             common_warnings::lead_zeros(&args);
             // Display loop
@@ -443,7 +497,7 @@ fn main() {
         CH510_PRODUCT_ID => {
             println!("Supported modes: {} [default: {}]", "cpu gpu".bold(), ch510::DEFAULT_MODE.symbol());
             // Connect to device
-            let ch510 = ch510::Display::new(cpu, gpu, &args.mode, args.update, args.fahrenheit);
+            let ch510 = ch510::Display::new(sampler, &args.mode, args.update, args.alarm, &config);
             // Print current configuration & warnings
             print_device_status(
                 &ch510.mode,
@@ -451,11 +505,17 @@ fn main() {
                 None,
                 None,
                 if args.fahrenheit { TemperatureUnit::Fahrenheit } else { TemperatureUnit::Celsius },
-                Alarm { state: AlarmState::NotSupported, temp_limit: 0, temp_warning: 0 },
+                Alarm {
+                    state: if ch510.alarm.enabled { AlarmState::On } else { AlarmState::Off },
+                    source: ch510.alarm.source.symbol().to_owned(),
+                    limit: ch510.alarm.limit,
+                    hysteresis: ch510.alarm.hysteresis,
+                    effect: ch510.alarm.effect,
+                    temp_warning: 0,
+                },
                 args.update,
             );
             common_warnings::secondary_mode(&args);
-            common_warnings::alarm(&args);
             common_warnings::rotate(&args);
             common_warnings::lead_zeros(&args);
             // Display loop
@@ -0,0 +1,64 @@
+//! Reads the CPU time of a cgroup (v2), e.g. a systemd slice or a container.
+//!
+//! The cgroup is selected by its path in the configuration file, relative to `/sys/fs/cgroup`:
+//! ```ini
+//! [cgroup]
+//! path = user.slice
+//! ```
+
+use super::stat;
+use crate::{error, utils::config::Config};
+use std::{fs::read_to_string, path::PathBuf, process::exit};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+pub struct Cgroup {
+    /// `cpu.stat` file of the cgroup
+    path: PathBuf,
+    cpus: usize,
+}
+
+impl Cgroup {
+    /// Reads the `[cgroup]` section, exits the program if the cgroup does not exist.
+    pub fn new(config: &Config) -> Option<Self> {
+        let section = config.sections("cgroup").last()?;
+        let selection = section.get("path").unwrap_or_else(|| section.missing_error("path"));
+
+        let relative = selection.strip_prefix(CGROUP_ROOT).unwrap_or(selection);
+        let path = PathBuf::from(CGROUP_ROOT).join(relative.trim_start_matches('/')).join("cpu.stat");
+        if !path.exists() {
+            error!(format!("Cgroup \"{selection}\" was not found"));
+            eprintln!("       Only cgroup v2 is supported, the path is relative to {CGROUP_ROOT}.");
+            exit(1);
+        }
+
+        let cpus = read_to_string("/sys/devices/system/cpu/online")
+            .map(|list| stat::parse_cpu_list(&list).len())
+            .unwrap_or(0);
+
+        Some(Cgroup { path, cpus: cpus.max(1) })
+    }
+
+    /// Reads the CPU time of the cgroup in microseconds.
+    pub fn read(&self) -> u64 {
+        let data = read_to_string(&self.path).unwrap_or_else(|_| {
+            error!("Failed to get cgroup usage");
+            exit(1);
+        });
+
+        data.lines()
+            .find_map(|line| line.strip_prefix("usage_usec "))
+            .and_then(|usage| usage.parse::<u64>().ok())
+            .unwrap_or(0)
+    }
+
+    /// Reads the CPU time one more time and calculates the utilization of all CPUs as a `0-100` number.
+    ///
+    /// Formula: `% = Δμs / (Δms * 1000 * CPUs) * 100`
+    pub fn get_usage(&self, initial_usage: u64, delta_millisec: u64) -> u8 {
+        let delta_usage = self.read().saturating_sub(initial_usage);
+        let usage = delta_usage as f64 / (delta_millisec * 1000 * self.cpus as u64) as f64 * 100.0;
+
+        usage.min(100.0).round() as u8
+    }
+}
@@ -0,0 +1,96 @@
+//! Reads the coolant temperature from the hwmon sensors of a liquid cooler or the motherboard.
+//!
+//! The sensor is discovered automatically by its label, or it can be selected in the configuration file:
+//! ```ini
+//! [coolant]
+//! chip = d5next
+//! # Input name or label of the temperature sensor
+//! channel = temp1
+//! ```
+
+use super::hwmon::{self, Calibration};
+use crate::{error, utils::config::Config, warning};
+use std::{fs::read_to_string, process::exit};
+
+/// Label fragments of the coolant temperature sensors.
+const COOLANT_LABELS: [&str; 3] = ["coolant", "water", "liquid"];
+
+pub struct Coolant {
+    input: Option<String>,
+    calibration: Calibration,
+}
+
+impl Coolant {
+    pub fn new(config: &Config) -> Self {
+        let section = config.sections("coolant").last();
+        let chip = section.and_then(|s| s.get("chip"));
+        let channel = section.and_then(|s| s.get("channel"));
+
+        match find_coolant_sensor(chip, channel) {
+            Some((name, input)) => Coolant {
+                input: Some(input),
+                calibration: Calibration::read(config, &name).unwrap_or(Calibration::NONE),
+            },
+            None => Coolant { input: None, calibration: Calibration::NONE },
+        }
+    }
+
+    /// Displays a warning message if the coolant sensor is not initialized.
+    pub fn warn_missing(&self) {
+        if self.input.is_none() {
+            warning!("No coolant temperature sensor was found");
+            eprintln!("         Coolant temperature will not be displayed.");
+            eprintln!("         The sensor can be selected in the [coolant] section of the configuration.");
+        }
+    }
+
+    /// Reads the value of the coolant temperature sensor and calculates it to be `˚C` or `˚F`.
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
+        if let Some(input) = &self.input {
+            let data = read_to_string(input).unwrap_or_else(|_| {
+                error!("Failed to get coolant temperature");
+                exit(1);
+            });
+            let temp = self.calibration.apply(data.trim_end().parse::<i32>().unwrap_or(0) as f32 / 1000.0);
+            return if fahrenheit { temp * 9.0 / 5.0 + 32.0 } else { temp };
+        }
+
+        0.0
+    }
+}
+
+/// Looks for the temperature input of the coolant in the hwmon directory.
+///
+/// Without a selected channel, the input labeled as coolant or water temperature is used.
+/// If only the chip is selected, its first temperature input is the fallback.
+/// Returns the name of the sensor as `chip:label` and the path of the input.
+fn find_coolant_sensor(chip: Option<&str>, channel: Option<&str>) -> Option<(String, String)> {
+    let mut fallback = None;
+    for sensor in hwmon::chips() {
+        if chip.is_some_and(|chip| sensor.name != chip) {
+            continue;
+        }
+
+        for temp in hwmon::inputs(&sensor.path, "temp") {
+            let input = (format!("{}:{}", sensor.name, temp.name("temp")), temp.path.to_str()?.to_owned());
+            match channel {
+                Some(channel) => {
+                    if temp.matches("temp", channel) {
+                        return Some(input);
+                    }
+                }
+                None => {
+                    let label = temp.label.as_deref().unwrap_or_default().to_lowercase();
+                    if COOLANT_LABELS.iter().any(|coolant| label.contains(coolant)) {
+                        return Some(input);
+                    }
+                    if chip.is_some() {
+                        fallback = fallback.or(Some(input));
+                    }
+                }
+            }
+        }
+    }
+
+    fallback
+}
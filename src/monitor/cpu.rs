@@ -1,87 +1,177 @@
 //! Reads live CPU data from the Linux kernel.
 
-use crate::{error, warning};
+use super::{cgroup::Cgroup, cpufreq::{self, Aggregate, CpuFreq}, hwmon::{self, Calibration}, perf, rapl::{self, Domain, Rapl}, stat::CpuTimes};
+use crate::{error, utils::config::Config, warning};
 use cpu_monitor::CpuInstant;
-use std::{fs::{read_dir, read_to_string, File}, io::{BufRead, BufReader}, process::exit};
+use std::{collections::HashMap, fs::{read_to_string, File}, io::{BufRead, BufReader}, process::exit};
+
+/// Candidates of the CPU temperature sensor in the order of priority as `(chip, label)`.
+///
+/// The `thermal` chip stands for the thermal zones labeled by their type.
+const TEMP_SENSORS: [(&str, &str); 16] = [
+    // Dedicated CPU drivers
+    ("coretemp", "temp1"),
+    ("k10temp", "Tdie"),
+    ("k10temp", "temp1"),
+    ("zenpower", "temp1"),
+    ("thermal", "x86_pkg_temp"),
+    // ARM SoCs
+    ("cpu_thermal", "temp1"),
+    ("thermal", "cpu-thermal"),
+    ("thermal", "cpu_thermal"),
+    ("thermal", "soc_thermal"),
+    // AMD APUs
+    ("amdgpu", "edge"),
+    // Motherboards & laptops
+    ("asusec", "CPU"),
+    ("nct6*", "CPUTIN"),
+    ("nct6*", "PECI Agent 0*"),
+    ("thinkpad", "CPU"),
+    // ACPI
+    ("acpitz", "temp1"),
+    ("thermal", "acpitz"),
+];
+
+/// Known offsets of the `Tctl` temperature of older Ryzen processors as `(model name, offset)`.
+///
+/// The model name matches all processors starting with it.
+const TCTL_OFFSETS: [(&str, f32); 6] = [
+    ("AMD Ryzen 5 1600X", 20.0),
+    ("AMD Ryzen 7 1700X", 20.0),
+    ("AMD Ryzen 7 1800X", 20.0),
+    ("AMD Ryzen 7 2700X", 10.0),
+    ("AMD Ryzen Threadripper 19", 27.0),
+    ("AMD Ryzen Threadripper 29", 27.0),
+];
+
+/// Temperature inputs of the CPU with the name they are displayed as (e.g. `k10temp:Tctl`).
+struct TempSensor {
+    name: String,
+    inputs: Vec<(String, Calibration)>,
+}
 
 pub struct Cpu {
-    temp_sensor: Option<String>,
-    rapl_max_uj: u64,
+    temp_sensor: Option<TempSensor>,
+    /// Energy counters of the power domains that are displayed
+    rapl: HashMap<Domain, Rapl>,
+    /// CPU socket selected for the power domains
+    socket: Option<u8>,
+    freq: CpuFreq,
+    performance_cores: Vec<usize>,
+    efficiency_cores: Vec<usize>,
+    cgroup: Option<Cgroup>,
 }
 
 impl Cpu {
-    pub fn new() -> Self {
+    /// Initializes the CPU monitoring, the temperature sensor can be selected as `chip:label`.
+    pub fn new(temp_sensor: Option<&str>, config: &Config) -> Self {
+        let temp_sensor = match temp_sensor {
+            Some(selection) => Some(find_selected_temp_sensor(selection, config)),
+            None => find_temp_sensor(config),
+        };
+
         Cpu {
-            temp_sensor: find_temp_sensor(),
-            rapl_max_uj: get_max_energy(),
+            temp_sensor,
+            rapl: HashMap::new(),
+            socket: rapl::read_socket(config),
+            freq: CpuFreq::new(config),
+            performance_cores: cpufreq::find_cluster(true),
+            efficiency_cores: cpufreq::find_cluster(false),
+            cgroup: Cgroup::new(config),
         }
     }
 
+    /// Returns the name of the temperature sensor as `chip:label`.
+    pub fn temp_sensor_name(&self) -> Option<&str> {
+        self.temp_sensor.as_ref().map(|sensor| sensor.name.as_str())
+    }
+
+    /// Returns the calibration of the temperature sensor (of the first input if multiple are selected).
+    pub fn temp_calibration(&self) -> Calibration {
+        self.temp_sensor
+            .as_ref()
+            .and_then(|sensor| sensor.inputs.first())
+            .map_or(Calibration::NONE, |(_, calibration)| *calibration)
+    }
+
     /// Displays a warning message if temperature sensor is not initialized.
     pub fn warn_temp(&self) {
-        if self.temp_sensor == None {
+        if self.temp_sensor.is_none() {
             warning!("No supported CPU temperature sensor was found");
             eprintln!("         CPU temperature will not be displayed, and alarm will be disabled.");
-            eprintln!("         Supported kernel modules are: asusec, coretemp, k10temp, and zenpower.");
+            eprintln!("         A sensor can be selected with --cpu-sensor from the list of --sensors.");
         }
     }
 
     /// Displays a warning message if RAPL module is not initialized.
     pub fn warn_rapl(&self) {
-        if self.rapl_max_uj == 0 {
+        self.warn_power(Domain::Package);
+    }
+
+    /// Displays a warning message if the energy counter of the power domain is not found.
+    pub fn warn_power(&self, domain: Domain) {
+        let rapl = match self.rapl.get(&domain) {
+            Some(rapl) => rapl,
+            None => return,
+        };
+        if let Some(denied) = rapl.denied() {
+            warning!(format!("Permission denied to read {}", denied.path));
+            if denied.perf {
+                let level = perf::get_paranoid_level().map_or("unknown".to_owned(), |level| level.to_string());
+                eprintln!("         The perf power events require perf_event_paranoid to be 0 or lower (currently {level}),");
+                eprintln!("         or the CAP_PERFMON capability.");
+            } else {
+                eprintln!("         The perf power events are not available as an alternative.");
+            }
+            eprintln!("         {} power consumption will not be displayed.", domain.description());
+            eprintln!("         Run the program as root, or see the Rootless Mode section of the README.");
+        } else if !rapl.is_found() && !rapl::is_present() {
             warning!("RAPL module was not found");
-            eprintln!("         CPU power consumption will not be displayed.");
+            eprintln!("         {} power consumption will not be displayed.", domain.description());
+            eprintln!("         Supported interfaces are: intel-rapl, intel-rapl-mmio, amd_energy, and zenergy.");
+        } else if !rapl.is_found() {
+            warning!(format!("RAPL zone \"{}\" was not found", domain.zone()));
+            eprintln!("         {} power consumption will not be displayed.", domain.description());
         }
     }
 
     /// Reads the value of the CPU temperature sensor and calculates it to be `˚C` or `˚F`.
-    pub fn get_temp(&self, fahrenheit: bool) -> u8 {
-        if let Some(sensor) = &self.temp_sensor {
+    ///
+    /// If multiple sensors are selected, the highest temperature is returned.
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
+        let sensor = match &self.temp_sensor {
+            Some(sensor) => sensor,
+            None => return 0.0,
+        };
+
+        let mut temp = f32::MIN;
+        for (input, calibration) in &sensor.inputs {
             // Read sensor data
-            let data = read_to_string(sensor).unwrap_or_else(|_| {
+            let data = read_to_string(input).unwrap_or_else(|_| {
                 error!("Failed to get CPU temperature");
                 exit(1);
             });
             // Calculate temperature
-            let mut temp = data.trim_end().parse::<u32>().unwrap();
-            if fahrenheit {
-                temp = temp * 9 / 5 + 32000
-            }
-            return (temp as f32 / 1000.0).round() as u8;
+            temp = temp.max(calibration.apply(data.trim_end().parse::<i32>().unwrap() as f32 / 1000.0));
         }
 
-        0
+        if fahrenheit { temp * 9.0 / 5.0 + 32.0 } else { temp }
     }
 
-    /// Reads the energy consumption of the CPU in microjoules.
-    pub fn read_energy(&self) -> u64 {
-        if self.rapl_max_uj > 0 {
-            let data = read_to_string("/sys/class/powercap/intel-rapl/intel-rapl:0/energy_uj").unwrap_or_else(|_| {
-                error!("Failed to get CPU power");
-                exit(1);
-            });
-            return data.trim_end().parse::<u64>().unwrap();
-        }
-
-        0
+    /// Looks for the energy counters of the power domain, if they are not initialized yet.
+    pub fn require_power(&mut self, domain: Domain) {
+        let socket = self.socket;
+        self.rapl.entry(domain).or_insert_with(|| Rapl::new(domain, socket));
     }
 
-    /// Reads the energy consumption one more time and calculates the CPU power by using the inital energy and the delta time.
-    ///
-    /// Formula: `W = ΔμJ / (Δms * 1000)`
-    pub fn get_power(&self, initial_energy: u64, delta_millisec: u64) -> u16 {
-        if self.rapl_max_uj > 0 {
-            let current_energy = self.read_energy();
-            let delta_energy = if current_energy > initial_energy {
-                current_energy - initial_energy
-            } else {
-                // Offset the current measurement if the counter resets
-                (self.rapl_max_uj + current_energy) - initial_energy
-            };
-            return (delta_energy as f64 / (delta_millisec * 1000) as f64).round() as u16;
-        }
+    /// Reads the energy consumption of the power domain in microjoules.
+    pub fn read_energy(&self, domain: Domain) -> Vec<u64> {
+        self.rapl.get(&domain).map_or(Vec::new(), |rapl| rapl.read())
+    }
 
-        0
+    /// Reads the energy consumption one more time and calculates the power of the domain by using the inital energy and the delta time.
+    pub fn get_power(&self, domain: Domain, initial_energy: &[u64], delta_millisec: u64) -> u16 {
+        self.rapl.get(&domain).map_or(0, |rapl| rapl.get_power(initial_energy, delta_millisec))
     }
 
     /// Reads the CPU instant and provides usage statistics.
@@ -99,48 +189,166 @@ impl Cpu {
         (usage).round() as u8
     }
 
-    /// Reads the frequency of all CPU cores and returns the highest one in MHz.
-    pub fn get_frequency(&self) -> u16 {
-        let cpuinfo = read_to_string("/proc/cpuinfo").unwrap_or_else(|_| {
-            error!("Failed to get CPU clock");
+    /// Exits the program if the performance or efficiency cores are not found.
+    pub fn verify_cluster(&self, performance: bool) {
+        let cores = if performance { &self.performance_cores } else { &self.efficiency_cores };
+        if cores.is_empty() {
+            error!(format!("No {} cores were found", if performance { "performance" } else { "efficiency" }));
+            eprintln!("       Clusters are only available on hybrid processors.");
             exit(1);
-        });
+        }
+    }
 
-        let mut highest_core = 0.0;
-        for info in cpuinfo.lines() {
-            if info.starts_with("cpu MHz") {
-                let clock = info.split(":").nth(1).unwrap();
-                let clock = clock.trim().parse::<f32>().unwrap();
-                if clock > highest_core {
-                    highest_core = clock;
-                }
-            }
+    /// Exits the program if the cores selected for the frequency are not found.
+    pub fn verify_frequency(&self) {
+        if let Some(performance) = self.freq.cluster() {
+            self.verify_cluster(performance);
+        }
+    }
+
+    /// Exits the program if no cgroup is configured.
+    pub fn verify_cgroup(&self) {
+        if self.cgroup.is_none() {
+            error!("No cgroup is configured for the cgroup usage");
+            eprintln!("       The cgroup can be selected with the path of the [cgroup] section.");
+            exit(1);
         }
+    }
 
-        highest_core.round() as u16
+    /// Reads the CPU time of the cgroup in microseconds.
+    pub fn read_cgroup_time(&self) -> u64 {
+        self.cgroup.as_ref().map_or(0, |cgroup| cgroup.read())
+    }
+
+    /// Reads the CPU time of the cgroup one more time and calculates its utilization as a `0-100` number.
+    pub fn get_cgroup_usage(&self, initial_time: u64, delta_millisec: u64) -> u8 {
+        self.cgroup.as_ref().map_or(0, |cgroup| cgroup.get_usage(initial_time, delta_millisec))
+    }
+
+    /// Reads the core times one more time and calculates the utilization of the busiest core as a `0-100` number.
+    pub fn get_max_core_usage(&self, initial_times: &CpuTimes) -> u8 {
+        let usage = CpuTimes::read()
+            .core_usage_since(initial_times)
+            .into_iter()
+            .map(|(_, usage)| usage)
+            .fold(0.0, f32::max);
+
+        (usage * 100.0).round() as u8
+    }
+
+    /// Reads the core times one more time and calculates the average utilization of the performance
+    /// or efficiency cores as a `0-100` number.
+    pub fn get_cluster_usage(&self, performance: bool, initial_times: &CpuTimes) -> u8 {
+        let cores = if performance { &self.performance_cores } else { &self.efficiency_cores };
+        let usage: Vec<f32> = CpuTimes::read()
+            .core_usage_since(initial_times)
+            .into_iter()
+            .filter(|(id, _)| cores.contains(id))
+            .map(|(_, usage)| usage)
+            .collect();
+        if usage.is_empty() {
+            return 0;
+        }
+
+        (usage.iter().sum::<f32>() / usage.len() as f32 * 100.0).round() as u8
+    }
+
+    /// Reads the CPU times one more time and calculates the utilization without the I/O wait
+    /// and the time stolen by the hypervisor as a `0-100` number.
+    pub fn get_active_usage(&self, initial_times: &CpuTimes) -> u8 {
+        let usage = CpuTimes::read().total.active_usage_since(&initial_times.total);
+
+        (usage * 100.0).round() as u8
+    }
+
+    /// Checks if the frequency is calculated from the utilization of the cores.
+    pub fn frequency_uses_core_times(&self) -> bool {
+        self.freq.aggregate() == Aggregate::BusyAverage
+    }
+
+    /// Reads the frequency of the CPU cores and returns the aggregated value in MHz.
+    pub fn get_frequency(&self, initial_times: &CpuTimes) -> u16 {
+        let cores = self.freq.cluster().map(|performance| {
+            if performance { self.performance_cores.as_slice() } else { self.efficiency_cores.as_slice() }
+        });
+        self.freq.get_frequency(initial_times, cores)
     }
 }
 
-/// Looks for the appropriate CPU temperature sensor datastream in the hwmon directory.
-fn find_temp_sensor() -> Option<String> {
-    for sensor in read_dir("/sys/class/hwmon").ok()? {
-        let path = sensor.ok()?.path().to_str()?.to_owned();
-        if let Ok(name) = read_to_string(format!("{path}/name")) {
-            if ["asusec", "coretemp", "k10temp", "zenpower"].contains(&name.trim_end()) {
-                return Some(format!("{path}/temp1_input"));
-            }
+/// Looks for the CPU temperature sensor with the highest priority in the hwmon directory and thermal zones.
+///
+/// Only the integrated GPU of an APU is considered from the `amdgpu` chips, which have no fan sensor.
+fn find_temp_sensor(config: &Config) -> Option<TempSensor> {
+    let chips: Vec<hwmon::Chip> = hwmon::chips()
+        .into_iter()
+        .filter(|sensor| sensor.name != "amdgpu" || !sensor.path.join("fan1_input").exists())
+        .collect();
+
+    for (chip, channel) in TEMP_SENSORS {
+        let found = hwmon::find_temp_inputs(&chips, chip, channel)
+            .into_iter()
+            .find(|(_, input)| read_to_string(&input.path).is_ok_and(|data| data.trim_end().parse::<i32>().is_ok()));
+        if let Some((name, input)) = found {
+            let calibration = calibrate(&name, &input, config);
+            return Some(TempSensor {
+                name,
+                inputs: vec![(input.path.to_str()?.to_owned(), calibration)],
+            });
         }
     }
 
     None
 }
 
-/// Gets the limit of the displayed energy value so it can be applied as an offset when the counter resets.
-fn get_max_energy() -> u64 {
-    match read_to_string("/sys/class/powercap/intel-rapl/intel-rapl:0/max_energy_range_uj") {
-        Ok(data) => data.trim_end().parse::<u64>().unwrap(),
-        Err(_) => 0,
+/// Looks for the temperature inputs selected as `chip:label`, exits the program if none of them is found.
+///
+/// The label can also be the name of the input (e.g. `temp2`), or end with `*` to select all matching inputs.
+/// Without a label, the first input of the chip is used.
+fn find_selected_temp_sensor(selection: &str, config: &Config) -> TempSensor {
+    let (chip, channel) = match selection.split_once(':') {
+        Some((chip, label)) => (chip, label),
+        None => (selection, "temp1"),
+    };
+
+    let inputs = hwmon::find_temp_inputs(&hwmon::chips(), chip, channel);
+    if inputs.is_empty() {
+        error!(format!("CPU temperature sensor \"{selection}\" was not found"));
+        eprintln!("       Available sensors can be listed with --sensors.");
+        exit(1);
+    }
+
+    TempSensor {
+        // Show the exact input if the selection matches only one
+        name: if inputs.len() == 1 { inputs[0].0.clone() } else { selection.to_owned() },
+        inputs: inputs
+            .iter()
+            .filter_map(|(name, input)| Some((input.path.to_str()?.to_owned(), calibrate(name, input, config))))
+            .collect(),
+    }
+}
+
+/// Returns the calibration of the input from the configuration.
+///
+/// Without a configured calibration, the known offset of the `Tctl` temperature is applied,
+/// unless the driver reports the corrected `Tdie` temperature as well.
+fn calibrate(name: &str, input: &hwmon::Input, config: &Config) -> Calibration {
+    if let Some(calibration) = Calibration::read(config, name) {
+        return calibration;
+    }
+
+    if name == "k10temp:Tctl" {
+        let has_tdie = input.path.parent().is_some_and(|path| {
+            hwmon::inputs(path, "temp").iter().any(|input| input.label.as_deref() == Some("Tdie"))
+        });
+        let offset = get_name().and_then(|model| {
+            TCTL_OFFSETS.iter().find(|(prefix, _)| model.starts_with(prefix)).map(|(_, offset)| *offset)
+        });
+        if let (false, Some(offset)) = (has_tdie, offset) {
+            return Calibration { offset: -offset, scale: 1.0 };
+        }
     }
+
+    Calibration::NONE
 }
 
 /// Gets the CPU model name.
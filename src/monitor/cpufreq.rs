@@ -0,0 +1,175 @@
+//! Reads the clock of the CPU cores from the cpufreq interface, or from `/proc/cpuinfo` if it is not available.
+//!
+//! The core clocks are combined into a single value, which can be changed in the configuration file:
+//! ```ini
+//! [cpu_freq]
+//! # max (default), average, or busy_average
+//! aggregate = busy_average
+//! # all (default), performance, or efficiency
+//! cores = performance
+//! ```
+
+use super::stat::{self, CpuTimes};
+use crate::{error, utils::config::Config};
+use std::{fs::{read_dir, read_to_string}, path::PathBuf, process::exit};
+
+/// Minimum utilization of the cores included in the busy average.
+const BUSY_USAGE: f32 = 0.1;
+
+/// Method of combining the clocks of the cores.
+#[derive(PartialEq, Clone, Copy)]
+pub enum Aggregate {
+    Max,
+    Average,
+    /// Average of the cores that are utilized at least 10% during the update.
+    BusyAverage,
+}
+
+pub struct CpuFreq {
+    aggregate: Aggregate,
+    /// Selected cluster (`true` for the performance cores), or `None` for all cores.
+    cluster: Option<bool>,
+    /// `scaling_cur_freq` files of the cores, empty if cpufreq is not available.
+    inputs: Vec<(usize, PathBuf)>,
+}
+
+impl CpuFreq {
+    pub fn new(config: &Config) -> Self {
+        let section = config.sections("cpu_freq").last();
+        let aggregate = match section.and_then(|s| s.get("aggregate").map(|value| (s, value))) {
+            Some((_, "max")) | None => Aggregate::Max,
+            Some((_, "average")) => Aggregate::Average,
+            Some((_, "busy_average")) => Aggregate::BusyAverage,
+            Some((section, value)) => section.value_error("aggregate", value),
+        };
+        let cluster = match section.and_then(|s| s.get("cores").map(|value| (s, value))) {
+            Some((_, "all")) | None => None,
+            Some((_, "performance")) => Some(true),
+            Some((_, "efficiency")) => Some(false),
+            Some((section, value)) => section.value_error("cores", value),
+        };
+
+        let mut inputs: Vec<(usize, PathBuf)> = match read_dir("/sys/devices/system/cpu") {
+            Ok(dir) => dir
+                .flatten()
+                .filter_map(|entry| {
+                    let id = entry.file_name().to_str()?.strip_prefix("cpu")?.parse::<usize>().ok()?;
+                    let path = entry.path().join("cpufreq/scaling_cur_freq");
+                    if path.exists() { Some((id, path)) } else { None }
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        inputs.sort_by_key(|(id, _)| *id);
+
+        CpuFreq { aggregate, cluster, inputs }
+    }
+
+    pub fn aggregate(&self) -> Aggregate {
+        self.aggregate
+    }
+
+    pub fn cluster(&self) -> Option<bool> {
+        self.cluster
+    }
+
+    /// Reads the clock of the cores and combines them in MHz, limited to the cores of the selected cluster.
+    ///
+    /// The busy average requires the core times from the start of the update.
+    pub fn get_frequency(&self, initial_times: &CpuTimes, cluster_cores: Option<&[usize]>) -> u16 {
+        let mut clocks = if self.inputs.is_empty() { read_cpuinfo() } else { self.read_cpufreq() };
+        if let Some(cores) = cluster_cores {
+            clocks.retain(|(id, _)| cores.contains(id));
+        }
+
+        let average = |clocks: &[(usize, f32)]| clocks.iter().map(|(_, clock)| clock).sum::<f32>() / clocks.len().max(1) as f32;
+        let frequency = match self.aggregate {
+            Aggregate::Max => clocks.iter().map(|(_, clock)| *clock).fold(0.0, f32::max),
+            Aggregate::Average => average(&clocks),
+            Aggregate::BusyAverage => {
+                let usage = CpuTimes::read().core_usage_since(initial_times);
+                let busy: Vec<(usize, f32)> = clocks
+                    .iter()
+                    .filter(|(id, _)| usage.iter().any(|(core, usage)| core == id && *usage >= BUSY_USAGE))
+                    .copied()
+                    .collect();
+                // Show the average of all cores if the CPU is idle
+                if busy.is_empty() { average(&clocks) } else { average(&busy) }
+            }
+        };
+
+        frequency.round() as u16
+    }
+
+    /// Reads the current clock of the cores from cpufreq in MHz.
+    fn read_cpufreq(&self) -> Vec<(usize, f32)> {
+        self.inputs
+            .iter()
+            .filter_map(|(id, path)| {
+                // Values are in kHz
+                let clock = read_to_string(path).ok()?.trim_end().parse::<u32>().ok()?;
+                Some((*id, clock as f32 / 1000.0))
+            })
+            .collect()
+    }
+}
+
+/// Reads the clock of the cores from `/proc/cpuinfo` in MHz.
+fn read_cpuinfo() -> Vec<(usize, f32)> {
+    let cpuinfo = read_to_string("/proc/cpuinfo").unwrap_or_else(|_| {
+        error!("Failed to get CPU clock");
+        exit(1);
+    });
+
+    let mut clocks = Vec::new();
+    let mut id = 0;
+    for info in cpuinfo.lines() {
+        let value = || info.split(':').nth(1).unwrap_or_default().trim();
+        if info.starts_with("processor") {
+            id = value().parse::<usize>().unwrap_or(id);
+        } else if info.starts_with("cpu MHz") {
+            if let Ok(clock) = value().parse::<f32>() {
+                clocks.push((id, clock));
+            }
+        }
+    }
+
+    clocks
+}
+
+/// Returns the performance or efficiency cores of a hybrid processor.
+///
+/// The clusters are reported by the `cpu_core` and `cpu_atom` PMUs on Intel,
+/// otherwise the cores are grouped by their `cpu_capacity` (ARM big.LITTLE).
+pub fn find_cluster(performance: bool) -> Vec<usize> {
+    let pmu = if performance { "cpu_core" } else { "cpu_atom" };
+    if let Ok(list) = read_to_string(format!("/sys/devices/{pmu}/cpus")) {
+        return stat::parse_cpu_list(&list);
+    }
+
+    let capacities: Vec<(usize, u32)> = match read_dir("/sys/devices/system/cpu") {
+        Ok(dir) => dir
+            .flatten()
+            .filter_map(|entry| {
+                let id = entry.file_name().to_str()?.strip_prefix("cpu")?.parse::<usize>().ok()?;
+                let capacity = read_to_string(entry.path().join("cpu_capacity")).ok()?.trim_end().parse::<u32>().ok()?;
+                Some((id, capacity))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    let highest = capacities.iter().map(|(_, capacity)| *capacity).max().unwrap_or(0);
+    // A processor with identical cores has no clusters
+    if capacities.iter().all(|(_, capacity)| *capacity == highest) {
+        return Vec::new();
+    }
+
+    let mut cores: Vec<usize> = capacities
+        .into_iter()
+        .filter(|(_, capacity)| (*capacity == highest) == performance)
+        .map(|(id, _)| id)
+        .collect();
+    cores.sort();
+
+    cores
+}
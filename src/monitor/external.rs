@@ -0,0 +1,192 @@
+//! Reads a number from an external source, e.g. a script reporting the length of a queue.
+//!
+//! The source is set in the configuration file, and displayed as the `external` metric:
+//! ```ini
+//! [external]
+//! # Number read from a file on each update
+//! file = /run/queue/length
+//! # Output of a command, executed every 5 seconds and killed after 1 second
+//! command = my-script --count
+//! interval = 5000
+//! timeout = 1000
+//! # Lines written to the standard input
+//! stdin = true
+//! # percent (default), celsius, watt, gigabyte, or none
+//! unit = celsius
+//! ```
+
+use crate::{error, utils::config::Config};
+use libc::{kill, pid_t, SIGKILL};
+use std::{
+    fs::read_to_string,
+    io::{stdin, BufRead, Read},
+    path::PathBuf,
+    os::unix::process::CommandExt,
+    process::{exit, Command, Stdio},
+    sync::{mpsc::channel, Arc, Mutex, OnceLock},
+    thread::{sleep, spawn},
+    time::{Duration, Instant},
+};
+
+/// Unit of the configured source, the metrics refer to it without the sampler (e.g. to check if it is a temperature).
+static UNIT: OnceLock<Unit> = OnceLock::new();
+
+/// Unit of the value, used for the glyph of the displays.
+#[derive(PartialEq, Clone, Copy)]
+pub enum Unit {
+    Percent,
+    /// Values in `˚C`, converted to `˚F` if it is selected.
+    Celsius,
+    Watt,
+    Gigabyte,
+    None,
+}
+
+enum Source {
+    File(PathBuf),
+    Command { command: String, interval: Duration, timeout: Duration },
+    Stdin,
+}
+
+pub struct External {
+    source: Option<Source>,
+    /// Last value of the command or the standard input, updated in the background.
+    value: Arc<Mutex<f32>>,
+    started: bool,
+}
+
+impl External {
+    /// Reads the `[external]` section, the source is only started once the metric is required.
+    pub fn new(config: &Config) -> Self {
+        let section = match config.sections("external").last() {
+            Some(section) => section,
+            None => {
+                return External { source: None, value: Arc::new(Mutex::new(0.0)), started: false };
+            }
+        };
+
+        let stdin = section.parse::<bool>("stdin").unwrap_or(false);
+        let source = match (section.get("file"), section.get("command"), stdin) {
+            (Some(file), None, false) => Source::File(PathBuf::from(file)),
+            (None, Some(command), false) => Source::Command {
+                command: command.to_owned(),
+                interval: Duration::from_millis(section.parse::<u64>("interval").unwrap_or(1000)),
+                timeout: Duration::from_millis(section.parse::<u64>("timeout").unwrap_or(1000)),
+            },
+            (None, None, true) => Source::Stdin,
+            (None, None, false) => section.missing_error("file"),
+            _ => {
+                error!("Only one of \"file\", \"command\", or \"stdin\" can be set in [external]");
+                exit(1);
+            }
+        };
+        let unit = match section.get("unit") {
+            Some("percent") | None => Unit::Percent,
+            Some("celsius") => Unit::Celsius,
+            Some("watt") => Unit::Watt,
+            Some("gigabyte") => Unit::Gigabyte,
+            Some("none") => Unit::None,
+            Some(value) => section.value_error("unit", value),
+        };
+        UNIT.get_or_init(|| unit);
+
+        External { source: Some(source), value: Arc::new(Mutex::new(0.0)), started: false }
+    }
+
+    /// Starts reading the command or the standard input in the background, exits the program if no source is set.
+    pub fn start(&mut self) {
+        let source = match &self.source {
+            Some(source) => source,
+            None => {
+                error!("External metric is not configured");
+                eprintln!("       The source can be set in the [external] section of the configuration.");
+                exit(1);
+            }
+        };
+        if self.started {
+            return;
+        }
+        self.started = true;
+
+        let value = Arc::clone(&self.value);
+        match source {
+            Source::File(_) => (),
+            Source::Command { command, interval, timeout } => {
+                let (command, interval, timeout) = (command.clone(), *interval, *timeout);
+                spawn(move || loop {
+                    // The value is cleared if the command fails, like an unreadable sensor
+                    *value.lock().unwrap() = run_command(&command, timeout).unwrap_or(0.0);
+                    sleep(interval);
+                });
+            }
+            Source::Stdin => {
+                spawn(move || {
+                    for line in stdin().lock().lines().map_while(Result::ok) {
+                        if let Some(number) = parse_number(&line) {
+                            *value.lock().unwrap() = number;
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    /// Returns the last value of the source, or `0` if it could not be read.
+    pub fn get_value(&self, fahrenheit: bool) -> f32 {
+        let value = match &self.source {
+            Some(Source::File(path)) => read_to_string(path).ok().and_then(|data| parse_number(&data)).unwrap_or(0.0),
+            Some(_) => *self.value.lock().unwrap(),
+            None => 0.0,
+        };
+
+        if fahrenheit && unit() == Unit::Celsius { value * 9.0 / 5.0 + 32.0 } else { value }
+    }
+}
+
+/// Returns the unit of the configured source.
+pub fn unit() -> Unit {
+    UNIT.get().copied().unwrap_or(Unit::Percent)
+}
+
+/// Runs the command in a shell and parses its output, the command is killed if it does not finish in time.
+fn run_command(command: &str, timeout: Duration) -> Option<f32> {
+    let deadline = Instant::now() + timeout;
+    // The shell and its children get their own process group, so all of them can be killed
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .ok()?;
+
+    // The output is read in the background, so the command is not blocked by a full pipe
+    let mut stdout = child.stdout.take()?;
+    let (sender, receiver) = channel();
+    spawn(move || {
+        let mut output = String::new();
+        let _ = sender.send(stdout.read_to_string(&mut output).map(|_| output));
+    });
+
+    let output = receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok().and_then(Result::ok);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if output.is_some() && Instant::now() < deadline => sleep(Duration::from_millis(10)),
+            _ => break None,
+        }
+    };
+    // Children of the shell may still hold the output open after the shell exits
+    if status.is_none() || output.is_none() {
+        unsafe { kill(-(child.id() as pid_t), SIGKILL) };
+        let _ = child.wait();
+    }
+
+    if status?.success() { parse_number(&output?) } else { None }
+}
+
+/// Parses the first word of the text as a number (e.g. `42.5` or `42.5 items`).
+fn parse_number(text: &str) -> Option<f32> {
+    text.split_whitespace().next()?.parse::<f32>().ok().filter(|number| number.is_finite())
+}
@@ -0,0 +1,94 @@
+//! Reads the CPU fan speed from the hwmon sensors of the motherboard.
+//!
+//! The fan is discovered automatically, or it can be selected in the configuration file:
+//! ```ini
+//! [cpu_fan]
+//! chip = nct6798
+//! # Input name or label of the fan
+//! channel = fan2
+//! ```
+
+use super::hwmon;
+use crate::{error, utils::config::Config, warning};
+use std::{fs::read_to_string, process::exit};
+
+/// Name prefixes of the supported motherboard sensor chips.
+const SUPPORTED_CHIPS: [&str; 6] = ["nct6", "it8", "asusec", "asus_wmi_sensors", "f71", "w83"];
+
+pub struct Fan {
+    input: Option<String>,
+}
+
+impl Fan {
+    pub fn new(config: &Config) -> Self {
+        let section = config.sections("cpu_fan").last();
+        let chip = section.and_then(|s| s.get("chip"));
+        let channel = section.and_then(|s| s.get("channel"));
+
+        Fan {
+            input: find_fan_sensor(chip, channel),
+        }
+    }
+
+    /// Checks if a fan sensor was found.
+    pub fn is_found(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Displays a warning message if the fan sensor is not initialized.
+    pub fn warn_missing(&self) {
+        if self.input.is_none() {
+            warning!("No supported CPU fan sensor was found");
+            eprintln!("         CPU fan speed will not be displayed.");
+            eprintln!("         Supported kernel modules are: asus-ec-sensors, asus_wmi_sensors, f71882fg, it87, nct6775, and w83627ehf.");
+        }
+    }
+
+    /// Reads the speed of the fan in RPM.
+    pub fn get_speed(&self) -> u16 {
+        if let Some(input) = &self.input {
+            let data = read_to_string(input).unwrap_or_else(|_| {
+                error!("Failed to get CPU fan speed");
+                exit(1);
+            });
+            return data.trim_end().parse::<u16>().unwrap_or(0);
+        }
+
+        0
+    }
+}
+
+/// Looks for the fan input in the hwmon directory.
+///
+/// Without a selected channel, the fan labeled as CPU fan is preferred, otherwise the first fan of the chip is used.
+fn find_fan_sensor(chip: Option<&str>, channel: Option<&str>) -> Option<String> {
+    let mut fallback = None;
+    for sensor in hwmon::chips() {
+        let chip_matches = match chip {
+            Some(chip) => sensor.name == chip,
+            None => SUPPORTED_CHIPS.iter().any(|prefix| sensor.name.starts_with(prefix)),
+        };
+        if !chip_matches {
+            continue;
+        }
+
+        for fan in hwmon::inputs(&sensor.path, "fan") {
+            let input = fan.path.to_str()?.to_owned();
+            match channel {
+                Some(channel) => {
+                    if fan.matches("fan", channel) {
+                        return Some(input);
+                    }
+                }
+                None => {
+                    if fan.label.as_deref().is_some_and(|l| l.to_lowercase().contains("cpu")) {
+                        return Some(input);
+                    }
+                    fallback = fallback.or(Some(input));
+                }
+            }
+        }
+    }
+
+    fallback
+}
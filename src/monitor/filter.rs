@@ -0,0 +1,128 @@
+//! Smooths the readings of the metrics before they are displayed.
+//!
+//! Filters are defined in the configuration file for each metric, and applied in the following order:
+//! ```ini
+//! [filter]
+//! metric = cpu_temp
+//! # Median of the last N samples
+//! median = 5
+//! # Exponential moving average (smoothing factor between 0 and 1)
+//! ema = 0.3
+//! # Hold the peak value for N milliseconds, then decrease it by N units per second
+//! peak_hold = 3000
+//! peak_decay = 2
+//! # Ignore changes smaller than N units
+//! deadband = 1
+//! ```
+
+use super::sampler::Metric;
+use crate::utils::config::{Config, Section};
+use std::{collections::{HashMap, VecDeque}, time::Duration};
+
+pub struct Filter {
+    median: usize,
+    ema: Option<f32>,
+    peak_hold: Option<Duration>,
+    peak_decay: f32,
+    deadband: f32,
+    history: VecDeque<f32>,
+    average: Option<f32>,
+    peak: Option<(f32, Duration)>,
+    output: Option<f32>,
+}
+
+impl Filter {
+    fn read(section: &Section) -> Self {
+        let ema = section.parse::<f32>("ema");
+        if let Some(alpha) = ema {
+            if alpha <= 0.0 || alpha > 1.0 {
+                section.value_error::<()>("ema", &alpha.to_string());
+            }
+        }
+
+        Filter {
+            median: section.parse::<usize>("median").unwrap_or(0),
+            ema,
+            peak_hold: section.parse::<u64>("peak_hold").map(Duration::from_millis),
+            peak_decay: section.parse::<f32>("peak_decay").unwrap_or(0.0).abs(),
+            deadband: section.parse::<f32>("deadband").unwrap_or(0.0).abs(),
+            history: VecDeque::new(),
+            average: None,
+            peak: None,
+            output: None,
+        }
+    }
+
+    /// Filters the new reading of the metric. The elapsed time is the length of the update.
+    pub fn apply(&mut self, value: f32, elapsed: Duration) -> f32 {
+        let mut value = value;
+
+        // Median
+        if self.median > 1 {
+            self.history.push_back(value);
+            if self.history.len() > self.median {
+                self.history.pop_front();
+            }
+            let mut sorted: Vec<f32> = self.history.iter().copied().collect();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            value = sorted[sorted.len() / 2];
+        }
+
+        // Exponential moving average
+        if let Some(alpha) = self.ema {
+            if let Some(average) = self.average {
+                value = average + alpha * (value - average);
+            }
+            self.average = Some(value);
+        }
+
+        // Peak-hold
+        if let Some(hold) = self.peak_hold {
+            value = match self.peak {
+                Some((peak, age)) if value < peak => {
+                    let age = age + elapsed;
+                    let held = if age <= hold {
+                        peak
+                    } else if self.peak_decay > 0.0 {
+                        peak - self.peak_decay * (age - hold).as_secs_f32()
+                    } else {
+                        value
+                    };
+                    if held > value {
+                        self.peak = Some((peak, age));
+                        held
+                    } else {
+                        self.peak = Some((value, Duration::ZERO));
+                        value
+                    }
+                }
+                _ => {
+                    self.peak = Some((value, Duration::ZERO));
+                    value
+                }
+            };
+        }
+
+        // Deadband
+        if let Some(output) = self.output {
+            if (value - output).abs() < self.deadband {
+                value = output;
+            }
+        }
+        self.output = Some(value);
+
+        value
+    }
+}
+
+/// Reads all `[filter]` sections of the configuration.
+pub fn read_filters(config: &Config) -> HashMap<Metric, Filter> {
+    config
+        .sections("filter")
+        .map(|section| {
+            let metric = section.get("metric").unwrap_or_else(|| section.missing_error("metric"));
+            let metric = Metric::get(metric).unwrap_or_else(|| section.value_error("metric", metric));
+            (metric, Filter::read(section))
+        })
+        .collect()
+}
@@ -32,7 +32,7 @@ impl Gpu {
     }
 
     /// Reads the value of the GPU temperature sensor and calculates it to be `˚C` or `˚F`.
-    pub fn get_temp(&self, fahrenheit: bool) -> u8 {
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
         // Read sensor data
         let data = read_to_string(format!("{}/temp1_input", &self.hwmon_dir)).unwrap_or_else(|_| {
             error!("Failed to get GPU temperature (AMD)");
@@ -40,12 +40,9 @@ impl Gpu {
         });
 
         // Calculate temperature
-        let mut temp = data.trim_end().parse::<u32>().unwrap();
-        if fahrenheit {
-            temp = temp * 9 / 5 + 32000
-        }
+        let temp = data.trim_end().parse::<i32>().unwrap() as f32 / 1000.0;
 
-        (temp as f32 / 1000.0).round() as u8
+        if fahrenheit { temp * 9.0 / 5.0 + 32.0 } else { temp }
     }
 
     /// Reads the value of the GPU usage in percentage.
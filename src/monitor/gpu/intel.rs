@@ -43,14 +43,11 @@ impl Gpu {
     }
 
     /// Reads GPU temperature
-    pub fn get_temp(&self, fahrenheit: bool) -> u8 {
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
         // Try reading standard temp1_input (common for xe, i915, and coretemp)
         if let Ok(data) = read_to_string(format!("{}/temp1_input", &self.hwmon_dir)) {
-            let mut temp = data.trim().parse::<u32>().unwrap_or(0);
-            if fahrenheit {
-                temp = temp * 9 / 5 + 32000;
-            }
-            return (temp as f32 / 1000.0).round() as u8;
+            let temp = data.trim().parse::<i32>().unwrap_or(0) as f32 / 1000.0;
+            return if fahrenheit { temp * 9.0 / 5.0 + 32.0 } else { temp };
         }
 
         // Fallback: Check for package temperature (B-series/other drivers)
@@ -60,18 +57,15 @@ impl Gpu {
 
             if let (Ok(label), Ok(data)) = (label, data) {
                 if label.trim().eq_ignore_ascii_case("pkg") || label.trim().eq_ignore_ascii_case("package id 0") {
-                    let mut temp = data.trim().parse::<u32>().unwrap_or(0);
-                    if fahrenheit {
-                        temp = temp * 9 / 5 + 32000;
-                    }
-                    return (temp as f32 / 1000.0).round() as u8;
+                    let temp = data.trim().parse::<i32>().unwrap_or(0) as f32 / 1000.0;
+                    return if fahrenheit { temp * 9.0 / 5.0 + 32.0 } else { temp };
                 }
             }
         }
 
         // If we are in fallback mode (coretemp), we expect to have found it above.
         // If not, return 0 instead of crashing.
-        0
+        0.0
     }
 
     /// Estimates GPU usage
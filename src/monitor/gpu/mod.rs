@@ -36,12 +36,12 @@ impl Gpu {
         }
     }
 
-    pub fn get_temp(&self, fahrenheit: bool) -> u8 {
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
         match &self {
             Gpu::Amd(amd) => amd.get_temp(fahrenheit),
             Gpu::Intel(intel) => intel.get_temp(fahrenheit),
             Gpu::Nvidia(nvidia) => nvidia.get_temp(fahrenheit),
-            Gpu::None => 0,
+            Gpu::None => 0.0,
         }
     }
 
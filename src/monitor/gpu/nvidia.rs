@@ -78,7 +78,7 @@ impl Gpu {
     }
 
     /// Reads the GPU temperature from the API and calculates it to be `˚C` or `˚F`.
-    pub fn get_temp(&self, fahrenheit: bool) -> u8 {
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
         let mut temp: u32 = 0;
         unsafe {
             let get_temp: Symbol<NvmlDeviceGetTemperature> = self.lib.get(b"nvmlDeviceGetTemperature").unwrap();
@@ -87,11 +87,8 @@ impl Gpu {
                 exit(1);
             }
         }
-        if fahrenheit {
-            temp = (temp as f32 * 9.0 / 5.0 + 32.0).round() as u32;
-        }
 
-        temp as u8
+        if fahrenheit { temp as f32 * 9.0 / 5.0 + 32.0 } else { temp as f32 }
     }
 
     /// Reads the GPU utilization from the API.
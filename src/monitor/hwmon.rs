@@ -0,0 +1,354 @@
+//! Lists the chips and sensor inputs of the hwmon devices, and the thermal zones of the kernel.
+//!
+//! Any sensor input can be displayed as a metric selected by `hwmon:<chip>/<input>` (e.g. `hwmon:nct6798/temp7`).
+//! The unit of the metric is inferred from the prefix of the input.
+
+use crate::{error, utils::config::Config};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+pub struct Chip {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// A sensor input of a chip, e.g. `temp2_input` with the label `Tccd1`.
+pub struct Input {
+    pub id: u8,
+    pub label: Option<String>,
+    pub path: PathBuf,
+}
+
+impl Input {
+    /// Returns the label of the input, or its name (e.g. `temp2`) if it is not labeled.
+    pub fn name(&self, prefix: &str) -> String {
+        self.label.clone().unwrap_or_else(|| format!("{prefix}{}", self.id))
+    }
+
+    /// Checks if the input matches the channel selected by its name or label.
+    ///
+    /// A trailing `*` matches all labels starting with the text before it.
+    pub fn matches(&self, prefix: &str, channel: &str) -> bool {
+        if channel == format!("{prefix}{}", self.id) {
+            return true;
+        }
+        let label = match &self.label {
+            Some(label) => label.to_lowercase(),
+            None => return false,
+        };
+        match channel.strip_suffix('*') {
+            Some(start) => label.starts_with(&start.to_lowercase()),
+            None => label == channel.to_lowercase(),
+        }
+    }
+}
+
+/// Returns all hwmon chips in the order of their device names.
+pub fn chips() -> Vec<Chip> {
+    let mut chips: Vec<(String, Chip)> = match read_dir("/sys/class/hwmon") {
+        Ok(dir) => dir
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let name = read_to_string(path.join("name")).ok()?.trim_end().to_owned();
+                Some((entry.file_name().to_str()?.to_owned(), Chip { name, path }))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    // Sort hwmon10 after hwmon9
+    chips.sort_by_key(|(device, _)| (device.len(), device.clone()));
+
+    chips.into_iter().map(|(_, chip)| chip).collect()
+}
+
+/// Returns the inputs of the given type (e.g. `temp` for `temp1_input`) in ascending order.
+pub fn inputs(path: &Path, prefix: &str) -> Vec<Input> {
+    let mut inputs: Vec<Input> = match read_dir(path) {
+        Ok(dir) => dir
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name();
+                let id = file_name.to_str()?.strip_prefix(prefix)?.strip_suffix("_input")?.parse::<u8>().ok()?;
+                let label = read_to_string(path.join(format!("{prefix}{id}_label"))).ok();
+                Some(Input {
+                    id,
+                    label: label.map(|l| l.trim_end().to_owned()),
+                    path: entry.path(),
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    inputs.sort_by_key(|input| input.id);
+
+    inputs
+}
+
+/// Returns the thermal zones as inputs labeled by their type (e.g. `x86_pkg_temp`) in ascending order.
+pub fn thermal_zones() -> Vec<Input> {
+    let mut zones: Vec<Input> = match read_dir("/sys/class/thermal") {
+        Ok(dir) => dir
+            .flatten()
+            .filter_map(|entry| {
+                let id = entry.file_name().to_str()?.strip_prefix("thermal_zone")?.parse::<u8>().ok()?;
+                let path = entry.path();
+                let zone_type = read_to_string(path.join("type")).ok()?;
+                Some(Input {
+                    id,
+                    label: Some(zone_type.trim_end().to_owned()),
+                    path: path.join("temp"),
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    zones.sort_by_key(|zone| zone.id);
+
+    zones
+}
+
+/// Returns the matching temperature inputs with their names as `chip:label`.
+///
+/// The chip name can end with `*` to match all chips starting with the text before it,
+/// the `thermal` chip stands for the thermal zones labeled by their type.
+pub fn find_temp_inputs(chips: &[Chip], chip: &str, channel: &str) -> Vec<(String, Input)> {
+    let prefix = if chip == "thermal" { "thermal_zone" } else { "temp" };
+    let candidates: Vec<(String, Input)> = if chip == "thermal" {
+        thermal_zones()
+            .into_iter()
+            .map(|zone| ("thermal".to_owned(), zone))
+            .collect()
+    } else {
+        chips
+            .iter()
+            .filter(|sensor| match chip.strip_suffix('*') {
+                Some(start) => sensor.name.starts_with(start),
+                None => sensor.name == chip,
+            })
+            .flat_map(|sensor| inputs(&sensor.path, "temp").into_iter().map(|input| (sensor.name.clone(), input)))
+            .collect()
+    };
+
+    candidates
+        .into_iter()
+        .filter(|(_, input)| input.matches(prefix, channel))
+        .map(|(chip, input)| (format!("{chip}:{}", input.name(prefix)), input))
+        .collect()
+}
+
+/// Correction of a temperature sensor that reads too high or too low, applied in `˚C` before any other processing.
+#[derive(PartialEq, Clone, Copy)]
+pub struct Calibration {
+    pub offset: f32,
+    pub scale: f32,
+}
+
+impl Calibration {
+    pub const NONE: Calibration = Calibration { offset: 0.0, scale: 1.0 };
+
+    /// Reads the last `[calibration]` section of the sensor named as `chip:label`.
+    ///
+    /// The sensor of the section can end with `*` to match all labels starting with the text before it.
+    pub fn read(config: &Config, name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let section = config.sections("calibration").filter(|section| {
+            let sensor = section.get("sensor").unwrap_or_else(|| section.missing_error("sensor")).to_lowercase();
+            match sensor.strip_suffix('*') {
+                Some(start) => name.starts_with(start),
+                None => name == sensor,
+            }
+        }).last()?;
+
+        let scale = section.parse::<f32>("scale").unwrap_or(1.0);
+        if scale <= 0.0 {
+            section.value_error::<()>("scale", &scale.to_string());
+        }
+
+        Some(Calibration {
+            offset: section.parse::<f32>("offset").unwrap_or(0.0),
+            scale,
+        })
+    }
+
+    /// Corrects the temperature in `˚C`.
+    pub fn apply(&self, temp: f32) -> f32 {
+        temp * self.scale + self.offset
+    }
+
+    /// Returns the settings of the calibration to be displayed, or `None` if it has no effect.
+    pub fn describe(&self) -> Option<String> {
+        let mut settings = Vec::new();
+        if self.offset != 0.0 {
+            settings.push(format!("offset: {:+}°C", self.offset));
+        }
+        if self.scale != 1.0 {
+            settings.push(format!("scale: {}", self.scale));
+        }
+
+        if settings.is_empty() { None } else { Some(settings.join(", ")) }
+    }
+}
+
+/// Unit of a sensor input, inferred from its prefix.
+#[derive(PartialEq, Clone, Copy)]
+pub enum Unit {
+    Temperature,
+    Fan,
+    Power,
+    Voltage,
+    Current,
+}
+
+impl Unit {
+    fn get(prefix: &str) -> Option<Unit> {
+        match prefix {
+            "temp" => Some(Self::Temperature),
+            "fan" => Some(Self::Fan),
+            "power" => Some(Self::Power),
+            "in" => Some(Self::Voltage),
+            "curr" => Some(Self::Current),
+            _ => None,
+        }
+    }
+
+    /// Returns the divisor of the raw value to get `˚C`, `RPM`, `W`, `V`, or `A`.
+    const fn scale(&self) -> f32 {
+        match self {
+            Unit::Temperature => 1000.0,
+            Unit::Fan => 1.0,
+            Unit::Power => 1_000_000.0,
+            Unit::Voltage => 1000.0,
+            Unit::Current => 1000.0,
+        }
+    }
+}
+
+/// Returns the unit of a sensor selected as `hwmon:<chip>/<input>`.
+pub fn unit(symbol: &str) -> Unit {
+    parse(symbol).map_or(Unit::Temperature, |(_, _, unit)| unit)
+}
+
+/// Splits the symbol into the chip name, the input name (e.g. `temp7`), and its unit.
+pub fn parse(symbol: &str) -> Option<(&str, &str, Unit)> {
+    let (chip, input) = symbol.strip_prefix("hwmon:")?.split_once('/')?;
+    let prefix = input.trim_end_matches(|c: char| c.is_ascii_digit());
+    if chip.is_empty() || prefix.len() == input.len() {
+        return None;
+    }
+
+    Some((chip, input, Unit::get(prefix)?))
+}
+
+/// Reads the selected sensors, which are looked up on first use.
+#[derive(Default)]
+pub struct Sensors {
+    inputs: RefCell<HashMap<String, PathBuf>>,
+}
+
+impl Sensors {
+    /// Looks up the input of the sensor, exits the program if it is not found.
+    pub fn verify(&self, symbol: &str) {
+        if self.inputs.borrow().contains_key(symbol) {
+            return;
+        }
+
+        let (chip, input, _) = parse(symbol).unwrap();
+        let path = chips()
+            .into_iter()
+            .filter(|sensor| sensor.name == chip)
+            .map(|sensor| sensor.path.join(format!("{input}_input")))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| {
+                let prefix = input.trim_end_matches(|c: char| c.is_ascii_digit());
+                error!(format!("Sensor \"{symbol}\" was not found"));
+                eprintln!("       Available inputs can be listed with `grep . /sys/class/hwmon/hwmon*/{{name,{prefix}*_input}}`.");
+                exit(1);
+            });
+        self.inputs.borrow_mut().insert(symbol.to_owned(), path);
+    }
+
+    /// Reads the value of the sensor in its unit, temperatures are calculated to be `˚C` or `˚F`.
+    pub fn get_value(&self, symbol: &str, fahrenheit: bool) -> f32 {
+        self.verify(symbol);
+        let data = read_to_string(&self.inputs.borrow()[symbol]).unwrap_or_else(|_| {
+            error!(format!("Failed to read sensor \"{symbol}\""));
+            exit(1);
+        });
+
+        let unit = unit(symbol);
+        let value = data.trim_end().parse::<i64>().unwrap_or(0) as f32 / unit.scale();
+        if unit == Unit::Temperature && fahrenheit { value * 9.0 / 5.0 + 32.0 } else { value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_chip_and_input() {
+        let (chip, input, unit) = parse("hwmon:d5next/temp1").unwrap();
+        assert_eq!((chip, input), ("d5next", "temp1"));
+        assert!(unit == Unit::Temperature);
+        assert!(parse("hwmon:nct6798/fan12").is_some_and(|(_, input, unit)| input == "fan12" && unit == Unit::Fan));
+    }
+
+    #[test]
+    fn infers_the_unit_from_the_prefix() {
+        assert!(unit("hwmon:corsairpsu/power1") == Unit::Power);
+        assert!(unit("hwmon:nct6798/in0") == Unit::Voltage);
+        assert!(unit("hwmon:corsairpsu/curr2") == Unit::Current);
+        assert!(unit("hwmon:nct6798/fan2") == Unit::Fan);
+    }
+
+    #[test]
+    fn rejects_invalid_symbols() {
+        for symbol in [
+            "d5next/temp1",
+            "hwmon:d5next",
+            "hwmon:/temp1",
+            "hwmon:d5next/temp",
+            "hwmon:d5next/pwm1",
+            "hwmon:d5next/intrusion0",
+        ] {
+            assert!(parse(symbol).is_none(), "{symbol}");
+        }
+    }
+
+    #[test]
+    fn scales_the_raw_values() {
+        assert_eq!(45_500.0 / Unit::Temperature.scale(), 45.5);
+        assert_eq!(1450.0 / Unit::Fan.scale(), 1450.0);
+        assert_eq!(120_000_000.0 / Unit::Power.scale(), 120.0);
+        assert_eq!(12_096.0 / Unit::Voltage.scale(), 12.096);
+        assert_eq!(8_500.0 / Unit::Current.scale(), 8.5);
+    }
+
+    #[test]
+    fn matches_inputs_by_name_or_label() {
+        let input = Input {
+            id: 3,
+            label: Some("Tctl".to_owned()),
+            path: PathBuf::new(),
+        };
+        assert_eq!(input.name("temp"), "Tctl");
+        assert!(input.matches("temp", "temp3"));
+        assert!(input.matches("temp", "tctl"));
+        assert!(input.matches("temp", "TC*"));
+        assert!(!input.matches("temp", "Tccd1"));
+
+        let unlabeled = Input {
+            id: 2,
+            label: None,
+            path: PathBuf::new(),
+        };
+        assert_eq!(unlabeled.name("fan"), "fan2");
+        assert!(!unlabeled.matches("fan", "CPU*"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Reads live memory data from the Linux kernel.
+
+use crate::error;
+use std::{fs::read_to_string, process::exit};
+
+/// Reads the RAM utilization as a `0-100` number.
+pub fn get_ram_usage() -> u8 {
+    let meminfo = read_meminfo();
+    let total = meminfo.get("MemTotal:");
+    let available = meminfo.get("MemAvailable:");
+
+    percentage(total.saturating_sub(available), total)
+}
+
+/// Reads the used RAM in GB.
+pub fn get_ram_used() -> f32 {
+    let meminfo = read_meminfo();
+    let used = meminfo.get("MemTotal:").saturating_sub(meminfo.get("MemAvailable:"));
+
+    used as f32 / 1024.0 / 1024.0
+}
+
+/// Reads the swap utilization as a `0-100` number, or `0` without swap space.
+pub fn get_swap_usage() -> u8 {
+    let meminfo = read_meminfo();
+    let total = meminfo.get("SwapTotal:");
+    let free = meminfo.get("SwapFree:");
+
+    percentage(total.saturating_sub(free), total)
+}
+
+struct Meminfo(String);
+
+impl Meminfo {
+    /// Returns the value of the key in kB.
+    fn get(&self, key: &str) -> u64 {
+        self.0
+            .lines()
+            .find(|line| line.starts_with(key))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(0)
+    }
+}
+
+fn read_meminfo() -> Meminfo {
+    Meminfo(read_to_string("/proc/meminfo").unwrap_or_else(|_| {
+        error!("Failed to get memory usage");
+        exit(1);
+    }))
+}
+
+fn percentage(used: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 0;
+    }
+
+    (used as f64 / total as f64 * 100.0).round() as u8
+}
@@ -1,2 +1,17 @@
+pub mod cgroup;
+pub mod coolant;
 pub mod cpu;
+pub mod cpufreq;
+pub mod external;
+pub mod fan;
+pub mod filter;
 pub mod gpu;
+pub mod hwmon;
+pub mod memory;
+pub mod perf;
+pub mod process;
+pub mod psu;
+pub mod rapl;
+pub mod sampler;
+pub mod stat;
+pub mod system_temp;
@@ -0,0 +1,113 @@
+//! Reads the energy counters of the `power` PMU with `perf_event_open`.
+//!
+//! Unlike the powercap interface, this works without root privileges if `perf_event_paranoid` is `0` or lower,
+//! or if the program has the `CAP_PERFMON` capability.
+
+use libc::{c_int, c_ulong, syscall, SYS_perf_event_open};
+use std::{
+    fs::{read_to_string, File},
+    io::{Error, ErrorKind, Read},
+    os::fd::FromRawFd,
+};
+
+const PMU_PATH: &str = "/sys/bus/event_source/devices/power";
+
+/// First version of the `perf_event_attr` structure, the kernel fills the newer fields with zeros.
+#[repr(C)]
+struct PerfEventAttr {
+    event_type: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    bp_addr: u64,
+}
+
+pub enum PerfError {
+    /// The PMU or the event is not available.
+    NotSupported,
+    /// The event exists, but `perf_event_paranoid` or the missing capability does not allow to open it.
+    PermissionDenied,
+}
+
+/// Energy counter of a single package.
+pub struct EnergyEvent {
+    file: File,
+    /// Microjoules per count
+    scale: f64,
+}
+
+impl EnergyEvent {
+    /// Reads the counter in microjoules.
+    pub fn read(&self) -> Option<u64> {
+        let mut data = [0; 8];
+        (&self.file).read_exact(&mut data).ok()?;
+
+        Some((u64::from_ne_bytes(data) as f64 * self.scale) as u64)
+    }
+}
+
+/// Opens the energy event (e.g. `energy-pkg`) on the first CPU of each package, in the order of the packages.
+pub fn open_energy_events(event: &str) -> Result<Vec<EnergyEvent>, PerfError> {
+    let pmu_type = read_value(&format!("{PMU_PATH}/type"))?.parse::<u32>().map_err(|_| PerfError::NotSupported)?;
+    let config = read_value(&format!("{PMU_PATH}/events/{event}"))?;
+    let config = config
+        .strip_prefix("event=0x")
+        .and_then(|config| u64::from_str_radix(config, 16).ok())
+        .ok_or(PerfError::NotSupported)?;
+    // Joules per count
+    let scale = read_value(&format!("{PMU_PATH}/events/{event}.scale"))?.parse::<f64>().map_err(|_| PerfError::NotSupported)?;
+    let cpus: Vec<c_int> = read_value(&format!("{PMU_PATH}/cpumask"))?
+        .split(',')
+        .filter_map(|cpu| cpu.split('-').next()?.parse::<c_int>().ok())
+        .collect();
+
+    let attr = PerfEventAttr {
+        event_type: pmu_type,
+        size: size_of::<PerfEventAttr>() as u32,
+        config,
+        sample_period: 0,
+        sample_type: 0,
+        read_format: 0,
+        flags: 0,
+        wakeup_events: 0,
+        bp_type: 0,
+        bp_addr: 0,
+    };
+
+    unsafe {
+        let mut events = Vec::new();
+        for cpu in cpus {
+            // Count the events of all processes on the CPU
+            let fd = syscall(SYS_perf_event_open, &attr as *const PerfEventAttr, -1 as c_int, cpu, -1 as c_int, 0 as c_ulong);
+            if fd < 0 {
+                return Err(match Error::last_os_error().kind() {
+                    ErrorKind::PermissionDenied => PerfError::PermissionDenied,
+                    _ => PerfError::NotSupported,
+                });
+            }
+            events.push(EnergyEvent {
+                file: File::from_raw_fd(fd as c_int),
+                scale: scale * 1_000_000.0,
+            });
+        }
+
+        Ok(events)
+    }
+}
+
+/// Reads the `perf_event_paranoid` setting of the kernel.
+pub fn get_paranoid_level() -> Option<i8> {
+    read_to_string("/proc/sys/kernel/perf_event_paranoid").ok()?.trim_end().parse::<i8>().ok()
+}
+
+fn read_value(path: &str) -> Result<String, PerfError> {
+    match read_to_string(path) {
+        Ok(data) => Ok(data.trim_end().to_owned()),
+        Err(_) => Err(PerfError::NotSupported),
+    }
+}
@@ -0,0 +1,54 @@
+//! Reads the list of running processes from the `/proc` directory.
+
+use std::fs::{read, read_dir, read_to_string};
+
+pub struct Process {
+    /// Name of the process (limited to 15 characters by the kernel).
+    pub name: String,
+    /// Command line arguments separated by spaces.
+    pub cmdline: String,
+}
+
+impl Process {
+    /// Returns the file name of the executable from the command line.
+    pub fn executable(&self) -> &str {
+        let program = self.cmdline.split(' ').next().unwrap_or("");
+        program.rsplit('/').next().unwrap_or(program)
+    }
+}
+
+/// Gets all running processes.
+pub fn get_process_list() -> Vec<Process> {
+    let mut processes = Vec::new();
+    let dir = match read_dir("/proc") {
+        Ok(dir) => dir,
+        Err(_) => return processes,
+    };
+
+    for entry in dir.flatten() {
+        // Only look at the directories of the processes (PID)
+        let is_pid = entry.file_name().to_str().is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+
+        // The process can exit at any time, so skip it if the files cannot be read
+        let path = entry.path();
+        if let Ok(name) = read_to_string(path.join("comm")) {
+            let cmdline = match read(path.join("cmdline")) {
+                Ok(data) => String::from_utf8_lossy(&data)
+                    .split('\0')
+                    .filter(|arg| !arg.is_empty())
+                    .collect::<Vec<&str>>()
+                    .join(" "),
+                Err(_) => String::new(),
+            };
+            processes.push(Process {
+                name: name.trim_end().to_owned(),
+                cmdline,
+            });
+        }
+    }
+
+    processes
+}
@@ -0,0 +1,166 @@
+//! Reads live PSU data from the hwmon sensors of the power supply.
+//!
+//! Only Corsair PSUs are discovered automatically, other hwmon drivers must be selected in the configuration file.
+//! The load is calculated from the rated wattage of the PSU:
+//! ```ini
+//! [psu]
+//! chip = corsairpsu
+//! rated_power = 850
+//! ```
+
+use super::hwmon;
+use crate::{error, utils::config::Config, warning};
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+pub struct Psu {
+    temp_input: Option<String>,
+    power_input: Option<String>,
+    voltage_input: Option<String>,
+    current_input: Option<String>,
+    fan_input: Option<String>,
+    rated_power: Option<u16>,
+}
+
+impl Psu {
+    pub fn new(config: &Config) -> Self {
+        let section = config.sections("psu").last();
+        let chip = section.and_then(|s| s.get("chip"));
+        let rated_power = section.and_then(|s| s.parse::<u16>("rated_power"));
+
+        let mut psu = Psu {
+            temp_input: None,
+            power_input: None,
+            voltage_input: None,
+            current_input: None,
+            fan_input: None,
+            rated_power,
+        };
+        if let Some(path) = find_psu(chip) {
+            psu.temp_input = find_input(&path, "temp", &[]);
+            psu.power_input = find_input(&path, "power", &["total"]);
+            psu.voltage_input = find_input(&path, "in", &["v_in", "input"]);
+            psu.current_input = find_input(&path, "curr", &["curr in", "input", "total"]);
+            psu.fan_input = find_input(&path, "fan", &[]);
+        }
+
+        psu
+    }
+
+    /// Checks if the sensors of a PSU were found.
+    pub fn is_found(&self) -> bool {
+        self.power_input.is_some()
+            || self.temp_input.is_some()
+            || (self.voltage_input.is_some() && self.current_input.is_some())
+    }
+
+    /// Displays a warning message if the PSU sensors are not initialized.
+    pub fn warn_missing(&self) {
+        if !self.is_found() {
+            warning!("No supported PSU was found");
+            eprintln!("         PSU information will not be displayed.");
+            eprintln!("         Only corsair-psu is detected automatically, other hwmon drivers can be selected");
+            eprintln!("         with \"chip\" in the [psu] section of the configuration.");
+        } else if self.rated_power.is_none() {
+            warning!("Rated power of the PSU is not configured");
+            eprintln!("         PSU load will not be displayed.");
+        }
+    }
+
+    /// Checks if the PSU has a fan sensor.
+    pub fn has_fan(&self) -> bool {
+        self.fan_input.is_some()
+    }
+
+    /// Reads the value of the PSU temperature sensor and calculates it to be `˚C` or `˚F`.
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
+        match &self.temp_input {
+            Some(input) => {
+                let temp = read_value(input) as f32 / 1000.0;
+                if fahrenheit { temp * 9.0 / 5.0 + 32.0 } else { temp }
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Reads the power consumption of the PSU in watts.
+    ///
+    /// If the driver does not report the power, it is calculated from the input voltage and current.
+    pub fn get_power(&self) -> u16 {
+        match &self.power_input {
+            // Value is in microwatts
+            Some(input) => (read_value(input) as f64 / 1_000_000.0).round() as u16,
+            None => self.get_input_power(),
+        }
+    }
+
+    /// Calculates the input power of the PSU from the voltage and current in watts.
+    ///
+    /// Formula: `W = mV * mA / 1000000`
+    pub fn get_input_power(&self) -> u16 {
+        match (&self.voltage_input, &self.current_input) {
+            (Some(voltage), Some(current)) => {
+                (read_value(voltage) as f64 * read_value(current) as f64 / 1_000_000.0).round() as u16
+            }
+            _ => match &self.power_input {
+                Some(_) => self.get_power(),
+                None => 0,
+            },
+        }
+    }
+
+    /// Calculates the load of the PSU from the rated power as a `0-100` number.
+    pub fn get_usage(&self) -> u8 {
+        match self.rated_power {
+            Some(rated_power) if rated_power > 0 => {
+                (self.get_power() as f32 / rated_power as f32 * 100.0).round().min(100.0) as u8
+            }
+            _ => 0,
+        }
+    }
+
+    /// Reads the speed of the PSU fan in RPM.
+    pub fn get_fan_speed(&self) -> u16 {
+        match &self.fan_input {
+            Some(input) => read_value(input) as u16,
+            None => 0,
+        }
+    }
+}
+
+/// Reads a sensor value, exits the program if the PSU is disconnected.
+fn read_value(input: &str) -> i64 {
+    let data = read_to_string(input).unwrap_or_else(|_| {
+        error!("Failed to get PSU status");
+        exit(1);
+    });
+
+    data.trim_end().parse::<i64>().unwrap_or(0)
+}
+
+/// Looks for the hwmon directory of the PSU.
+fn find_psu(chip: Option<&str>) -> Option<PathBuf> {
+    hwmon::chips()
+        .into_iter()
+        .find(|sensor| sensor.name == chip.unwrap_or("corsairpsu"))
+        .map(|sensor| sensor.path)
+}
+
+/// Looks for a sensor input of the given type (e.g. `power` for `power1_input`).
+///
+/// The input with a label containing one of the preferred words is used, otherwise the first one.
+fn find_input(path: &Path, prefix: &str, preferred_labels: &[&str]) -> Option<String> {
+    let inputs = hwmon::inputs(path, prefix);
+    let input = inputs
+        .iter()
+        .find(|input| {
+            let label = input.label.as_deref().unwrap_or_default().to_lowercase();
+            preferred_labels.iter().any(|preferred| label.contains(preferred))
+        })
+        .or(inputs.first())?;
+
+    Some(input.path.to_str()?.to_owned())
+}
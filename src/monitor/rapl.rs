@@ -0,0 +1,271 @@
+//! Reads the energy counters of the CPU packages and their subdomains.
+//!
+//! The counters are discovered from the following interfaces in the order of priority:
+//! - `intel-rapl` powercap zones (Intel & AMD)
+//! - `intel-rapl-mmio` powercap zones (Intel laptops)
+//! - `energy*_input` hwmon sensors of the `amd_energy` or `zenergy` drivers (packages only)
+//! - `power/energy-*` events of perf, if the counters above are only readable by root
+//!
+//! The cores (`core`) and memory (`dram`) are subzones of the packages, while the platform (`psys`) has its own zone.
+//!
+//! The power of all packages is summed by default, a single socket can be selected in the configuration file:
+//! ```ini
+//! [cpu_power]
+//! socket = 0
+//! ```
+
+use super::{hwmon, perf::{self, EnergyEvent, PerfError}};
+use crate::{error, utils::config::Config};
+use std::{fs::{read_dir, read_to_string}, process::exit};
+
+/// Powercap interfaces of the package zones in the order of priority.
+const POWERCAP_INTERFACES: [&str; 2] = ["intel-rapl", "intel-rapl-mmio"];
+/// Names of the hwmon drivers reporting the energy of the sockets.
+const HWMON_DRIVERS: [&str; 2] = ["amd_energy", "zenergy"];
+
+/// Power domains of the RAPL interface.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Domain {
+    Package,
+    Core,
+    Dram,
+    Platform,
+}
+
+impl Domain {
+    /// Returns the name of the powercap zone.
+    pub const fn zone(&self) -> &'static str {
+        match self {
+            Domain::Package => "package",
+            Domain::Core => "core",
+            Domain::Dram => "dram",
+            Domain::Platform => "psys",
+        }
+    }
+
+    /// Returns the name of the perf event.
+    const fn event(&self) -> &'static str {
+        match self {
+            Domain::Package => "energy-pkg",
+            Domain::Core => "energy-cores",
+            Domain::Dram => "energy-ram",
+            Domain::Platform => "energy-psys",
+        }
+    }
+
+    /// Returns the name of the measured component.
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Domain::Package => "CPU",
+            Domain::Core => "CPU core",
+            Domain::Dram => "DRAM",
+            Domain::Platform => "System",
+        }
+    }
+}
+
+enum Source {
+    File(String),
+    Perf(EnergyEvent),
+}
+
+/// Energy counter of a single zone in microjoules.
+struct Counter {
+    source: Source,
+    socket: u8,
+    /// Range of the counter, or `0` if it is not known to wrap around.
+    max_uj: u64,
+}
+
+impl Counter {
+    fn read(&self) -> Option<u64> {
+        match &self.source {
+            Source::File(path) => read_to_string(path).ok()?.trim_end().parse::<u64>().ok(),
+            Source::Perf(event) => event.read(),
+        }
+    }
+}
+
+/// Permissions missing to read the energy counters.
+pub struct Denied {
+    /// Counter file that is only readable by root.
+    pub path: String,
+    /// Whether the perf events exist, but opening them is not permitted.
+    pub perf: bool,
+}
+
+pub struct Rapl {
+    counters: Vec<Counter>,
+    denied: Option<Denied>,
+}
+
+impl Rapl {
+    /// Looks for the energy counters of the domain, limited to a single socket if it is selected.
+    pub fn new(domain: Domain, socket: Option<u8>) -> Self {
+        let mut counters = find_powercap_counters(domain);
+        if counters.is_empty() && domain == Domain::Package {
+            counters = find_hwmon_counters();
+        }
+        // The counters are only readable by root on most systems since the PLATYPUS vulnerability
+        let mut denied = None;
+        let unreadable = counters.iter().find(|counter| counter.read().is_none()).and_then(|counter| match &counter.source {
+            Source::File(path) => Some(path.clone()),
+            Source::Perf(_) => None,
+        });
+        if let Some(path) = unreadable {
+            let perf = match perf::open_energy_events(domain.event()) {
+                Ok(events) => {
+                    counters = events
+                        .into_iter()
+                        .enumerate()
+                        .map(|(socket, event)| Counter {
+                            source: Source::Perf(event),
+                            socket: socket as u8,
+                            max_uj: 0,
+                        })
+                        .collect();
+                    None
+                }
+                Err(PerfError::PermissionDenied) => Some(true),
+                Err(PerfError::NotSupported) => Some(false),
+            };
+            if let Some(perf) = perf {
+                denied = Some(Denied { path, perf });
+                counters.clear();
+            }
+        }
+        // The platform zone covers all sockets
+        if let (Some(socket), false) = (socket, domain == Domain::Platform) {
+            counters.retain(|counter| counter.socket == socket);
+            if counters.is_empty() {
+                error!(format!("No {} energy counter was found for CPU socket {socket}", domain.zone()));
+                exit(1);
+            }
+        }
+
+        Rapl { counters, denied }
+    }
+
+    /// Checks if any energy counter was found.
+    pub fn is_found(&self) -> bool {
+        !self.counters.is_empty()
+    }
+
+    /// Returns the missing permissions if the energy counters exist, but cannot be read.
+    pub fn denied(&self) -> Option<&Denied> {
+        self.denied.as_ref()
+    }
+
+    /// Reads the energy counters of the packages in microjoules.
+    pub fn read(&self) -> Vec<u64> {
+        self.counters
+            .iter()
+            .map(|counter| {
+                counter.read().unwrap_or_else(|| {
+                    error!("Failed to get CPU power");
+                    exit(1);
+                })
+            })
+            .collect()
+    }
+
+    /// Reads the counters one more time and calculates the total power of the packages by using the initial energy and the delta time.
+    ///
+    /// Formula: `W = ΔμJ / (Δms * 1000)`
+    pub fn get_power(&self, initial_energy: &[u64], delta_millisec: u64) -> u16 {
+        let delta_energy: u64 = self
+            .counters
+            .iter()
+            .zip(self.read())
+            .zip(initial_energy)
+            .map(|((counter, current_energy), &initial_energy)| {
+                if current_energy >= initial_energy {
+                    current_energy - initial_energy
+                } else if counter.max_uj > 0 {
+                    // Offset the current measurement if the counter wraps around
+                    (counter.max_uj + current_energy) - initial_energy
+                } else {
+                    // Count from zero if the counter was reset
+                    current_energy
+                }
+            })
+            .sum();
+
+        (delta_energy as f64 / (delta_millisec * 1000) as f64).round() as u16
+    }
+}
+
+/// Checks if any RAPL interface is available, without reading its counters.
+pub fn is_present() -> bool {
+    !find_powercap_counters(Domain::Package).is_empty() || !find_hwmon_counters().is_empty()
+}
+
+/// Reads the CPU socket selected in the `[cpu_power]` section.
+pub fn read_socket(config: &Config) -> Option<u8> {
+    config.sections("cpu_power").last().and_then(|s| s.parse::<u8>("socket"))
+}
+
+/// Looks for the zones of the domain in the first available powercap interface.
+fn find_powercap_counters(domain: Domain) -> Vec<Counter> {
+    for interface in POWERCAP_INTERFACES {
+        let mut counters: Vec<Counter> = match read_dir("/sys/class/powercap") {
+            Ok(dir) => dir
+                .flatten()
+                .filter_map(|entry| {
+                    // Top-level zones are named as `intel-rapl:0`, their subzones as `intel-rapl:0:0`
+                    let zone = entry.file_name().to_str()?.to_owned();
+                    let ids: Vec<u8> = zone
+                        .strip_prefix(interface)?
+                        .strip_prefix(':')?
+                        .split(':')
+                        .map(|id| id.parse::<u8>().ok())
+                        .collect::<Option<_>>()?;
+                    let path = entry.path();
+                    let name = read_to_string(path.join("name")).ok()?.trim_end().to_owned();
+                    let socket = match (domain, ids.as_slice()) {
+                        (Domain::Package, [_]) => name.strip_prefix("package-")?.parse::<u8>().ok()?,
+                        (Domain::Platform, [_]) if name == domain.zone() => 0,
+                        (Domain::Core | Domain::Dram, [package, _]) if name == domain.zone() => {
+                            // The socket is the number of the parent package zone
+                            let parent = read_to_string(format!("/sys/class/powercap/{interface}:{package}/name")).ok()?;
+                            parent.trim_end().strip_prefix("package-")?.parse::<u8>().ok()?
+                        }
+                        _ => return None,
+                    };
+                    let max_uj = read_to_string(path.join("max_energy_range_uj")).ok()?.trim_end().parse::<u64>().ok()?;
+                    Some(Counter {
+                        source: Source::File(path.join("energy_uj").to_str()?.to_owned()),
+                        socket,
+                        max_uj,
+                    })
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        if !counters.is_empty() {
+            counters.sort_by_key(|counter| counter.socket);
+            return counters;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Looks for the socket energy sensors of the hwmon drivers (labeled as `Esocket0`).
+///
+/// These counters are 64-bit wide, so they are not expected to wrap around.
+fn find_hwmon_counters() -> Vec<Counter> {
+    hwmon::chips()
+        .iter()
+        .filter(|chip| HWMON_DRIVERS.contains(&chip.name.as_str()))
+        .flat_map(|chip| hwmon::inputs(&chip.path, "energy"))
+        .filter_map(|input| {
+            let socket = input.label.as_deref()?.strip_prefix("Esocket")?.parse::<u8>().ok()?;
+            Some(Counter {
+                source: Source::File(input.path.to_str()?.to_owned()),
+                socket,
+                max_uj: 0,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,327 @@
+//! Takes a single set of readings per update that can be shared by the display and the rule engine.
+
+use super::{coolant::Coolant, cpu::Cpu, external::{self, External}, fan::Fan, filter::{read_filters, Filter}, gpu::Gpu, hwmon::{self, Sensors}, memory, psu::Psu, rapl::Domain, stat::CpuTimes, system_temp::SystemTemp};
+use crate::utils::config::Config;
+use cpu_monitor::CpuInstant;
+use std::{cell::RefCell, collections::HashMap, thread::sleep, time::Duration};
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub enum Metric {
+    CpuTemperature,
+    CpuUsage,
+    CpuPower,
+    CpuCorePower,
+    CpuFrequency,
+    CpuFan,
+    GpuTemperature,
+    GpuUsage,
+    GpuPower,
+    GpuFrequency,
+    PsuTemperature,
+    PsuPower,
+    PsuInputPower,
+    PsuUsage,
+    PsuFan,
+    CoolantTemperature,
+    DramPower,
+    SystemPower,
+    RamUsage,
+    RamUsed,
+    SwapUsage,
+    DiskTemperature,
+    BoardTemperature,
+    CpuMaxCoreUsage,
+    CpuPerformanceUsage,
+    CpuEfficiencyUsage,
+    CpuActiveUsage,
+    CgroupUsage,
+    /// Sensor selected as `hwmon:<chip>/<input>`
+    Hwmon(String),
+    /// Number read from the source in the `[external]` section
+    External,
+}
+
+impl Metric {
+    pub fn symbol(&self) -> &str {
+        match self {
+            Metric::CpuTemperature => "cpu_temp",
+            Metric::CpuUsage => "cpu_usage",
+            Metric::CpuPower => "cpu_power",
+            Metric::CpuCorePower => "cpu_core_power",
+            Metric::CpuFrequency => "cpu_freq",
+            Metric::CpuFan => "cpu_fan",
+            Metric::GpuTemperature => "gpu_temp",
+            Metric::GpuUsage => "gpu_usage",
+            Metric::GpuPower => "gpu_power",
+            Metric::GpuFrequency => "gpu_freq",
+            Metric::PsuTemperature => "psu_temp",
+            Metric::PsuPower => "psu_power",
+            Metric::PsuInputPower => "psu_input_power",
+            Metric::PsuUsage => "psu_usage",
+            Metric::PsuFan => "psu_fan",
+            Metric::CoolantTemperature => "coolant_temp",
+            Metric::DramPower => "dram_power",
+            Metric::SystemPower => "system_power",
+            Metric::RamUsage => "ram_usage",
+            Metric::RamUsed => "ram_used_gb",
+            Metric::SwapUsage => "swap_usage",
+            Metric::DiskTemperature => "disk_temp",
+            Metric::BoardTemperature => "board_temp",
+            Metric::CpuMaxCoreUsage => "cpu_max_core_usage",
+            Metric::CpuPerformanceUsage => "cpu_p_core_usage",
+            Metric::CpuEfficiencyUsage => "cpu_e_core_usage",
+            Metric::CpuActiveUsage => "cpu_active_usage",
+            Metric::CgroupUsage => "cgroup_usage",
+            Metric::External => "external",
+            Metric::Hwmon(symbol) => symbol,
+        }
+    }
+
+    pub fn get(symbol: &str) -> Option<Metric> {
+        match symbol {
+            "cpu_temp" => Some(Self::CpuTemperature),
+            "cpu_usage" => Some(Self::CpuUsage),
+            "cpu_power" => Some(Self::CpuPower),
+            "cpu_core_power" => Some(Self::CpuCorePower),
+            "cpu_freq" => Some(Self::CpuFrequency),
+            "cpu_fan" => Some(Self::CpuFan),
+            "gpu_temp" => Some(Self::GpuTemperature),
+            "gpu_usage" => Some(Self::GpuUsage),
+            "gpu_power" => Some(Self::GpuPower),
+            "gpu_freq" => Some(Self::GpuFrequency),
+            "psu_temp" => Some(Self::PsuTemperature),
+            "psu_power" => Some(Self::PsuPower),
+            "psu_input_power" => Some(Self::PsuInputPower),
+            "psu_usage" => Some(Self::PsuUsage),
+            "psu_fan" => Some(Self::PsuFan),
+            "coolant_temp" => Some(Self::CoolantTemperature),
+            "dram_power" => Some(Self::DramPower),
+            "system_power" => Some(Self::SystemPower),
+            "ram_usage" => Some(Self::RamUsage),
+            "ram_used_gb" => Some(Self::RamUsed),
+            "swap_usage" => Some(Self::SwapUsage),
+            "disk_temp" => Some(Self::DiskTemperature),
+            "board_temp" => Some(Self::BoardTemperature),
+            "cpu_max_core_usage" => Some(Self::CpuMaxCoreUsage),
+            "cpu_p_core_usage" => Some(Self::CpuPerformanceUsage),
+            "cpu_e_core_usage" => Some(Self::CpuEfficiencyUsage),
+            "cpu_active_usage" => Some(Self::CpuActiveUsage),
+            "cgroup_usage" => Some(Self::CgroupUsage),
+            "external" => Some(Self::External),
+            _ if hwmon::parse(symbol).is_some() => Some(Self::Hwmon(symbol.to_owned())),
+            _ => None,
+        }
+    }
+
+    /// Checks if the metric is a temperature.
+    pub fn is_temperature(&self) -> bool {
+        matches!(
+            self,
+            Metric::CpuTemperature
+                | Metric::GpuTemperature
+                | Metric::PsuTemperature
+                | Metric::CoolantTemperature
+                | Metric::DiskTemperature
+                | Metric::BoardTemperature
+        ) || matches!(self, Metric::Hwmon(symbol) if hwmon::unit(symbol) == hwmon::Unit::Temperature)
+            || *self == Metric::External && external::unit() == external::Unit::Celsius
+    }
+
+    /// Returns the RAPL domain of the power metrics that are calculated from energy counters.
+    pub fn domain(&self) -> Option<Domain> {
+        match self {
+            Metric::CpuPower => Some(Domain::Package),
+            Metric::CpuCorePower => Some(Domain::Core),
+            Metric::DramPower => Some(Domain::Dram),
+            Metric::SystemPower => Some(Domain::Platform),
+            _ => None,
+        }
+    }
+}
+
+pub struct Sampler {
+    pub cpu: Cpu,
+    pub gpu: Gpu,
+    pub fan: Fan,
+    pub psu: Psu,
+    pub coolant: Coolant,
+    pub disk: SystemTemp,
+    pub board: SystemTemp,
+    pub hwmon: Sensors,
+    pub external: External,
+    fahrenheit: bool,
+    energy_domains: Vec<Domain>,
+    cpu_times: bool,
+    cgroup_time: bool,
+    /// Metrics used by the device, their filters are updated on every sample
+    required: Vec<Metric>,
+    filters: RefCell<HashMap<Metric, Filter>>,
+}
+
+impl Sampler {
+    pub fn new(cpu: Cpu, gpu: Gpu, fan: Fan, psu: Psu, coolant: Coolant, fahrenheit: bool, config: &Config) -> Self {
+        Sampler {
+            cpu,
+            gpu,
+            fan,
+            psu,
+            coolant,
+            disk: SystemTemp::disk(config),
+            board: SystemTemp::board(config),
+            hwmon: Sensors::default(),
+            external: External::new(config),
+            fahrenheit,
+            energy_domains: Vec::new(),
+            cpu_times: false,
+            cgroup_time: false,
+            required: Vec::new(),
+            filters: RefCell::new(read_filters(config)),
+        }
+    }
+
+    pub fn fahrenheit(&self) -> bool {
+        self.fahrenheit
+    }
+
+    pub fn set_fahrenheit(&mut self, fahrenheit: bool) {
+        self.fahrenheit = fahrenheit;
+    }
+
+    /// Displays a warning message if the sensor of the disk, motherboard, or coolant temperature is not found.
+    pub fn warn_missing_sensor(&self, metric: Metric) {
+        match metric {
+            Metric::CoolantTemperature => self.coolant.warn_missing(),
+            Metric::DiskTemperature => self.disk.warn_missing(),
+            Metric::BoardTemperature => self.board.warn_missing(),
+            _ => (),
+        }
+    }
+
+    /// Makes sure that the initial readings of the metric are taken before each update.
+    pub fn require(&mut self, metric: Metric) {
+        if let Some(domain) = metric.domain() {
+            if !self.energy_domains.contains(&domain) {
+                self.cpu.require_power(domain);
+                self.energy_domains.push(domain);
+            }
+        }
+        match &metric {
+            Metric::CpuFrequency => {
+                self.cpu.verify_frequency();
+                self.cpu_times |= self.cpu.frequency_uses_core_times();
+            }
+            Metric::CpuMaxCoreUsage | Metric::CpuActiveUsage => self.cpu_times = true,
+            Metric::CpuPerformanceUsage | Metric::CpuEfficiencyUsage => {
+                self.cpu.verify_cluster(metric == Metric::CpuPerformanceUsage);
+                self.cpu_times = true;
+            }
+            Metric::CgroupUsage => {
+                self.cpu.verify_cgroup();
+                self.cgroup_time = true;
+            }
+            Metric::Hwmon(symbol) => self.hwmon.verify(symbol),
+            Metric::External => self.external.start(),
+            _ => (),
+        }
+        if !self.required.contains(&metric) {
+            self.required.push(metric);
+        }
+    }
+
+    /// Takes the initial readings, waits for the update interval, and returns the sample of the timeframe.
+    pub fn sample(&self, update: Duration) -> Sample<'_> {
+        // Read CPU utilization, core & cgroup times, and energy consumption (if needed)
+        let cpu_instant = self.cpu.read_instant();
+        let cpu_energy = self.energy_domains.iter().map(|&domain| (domain, self.cpu.read_energy(domain))).collect();
+        let cpu_times = if self.cpu_times { CpuTimes::read() } else { CpuTimes::default() };
+        let cgroup_time = if self.cgroup_time { self.cpu.read_cgroup_time() } else { 0 };
+
+        // Wait
+        sleep(update);
+
+        let sample = Sample {
+            sampler: self,
+            cpu_instant,
+            cpu_energy,
+            cpu_times,
+            cgroup_time,
+            delta_millisec: update.as_millis() as u64,
+            values: RefCell::new(HashMap::new()),
+        };
+
+        // Filtered metrics are read even if they are not displayed, so the filters do not skip updates
+        self.required
+            .iter()
+            .filter(|metric| self.filters.borrow().contains_key(metric))
+            .for_each(|metric| {
+                sample.get(metric.clone());
+            });
+
+        sample
+    }
+}
+
+/// Readings of a single update. Each metric is read (and filtered) on first access and reused afterwards.
+pub struct Sample<'a> {
+    sampler: &'a Sampler,
+    cpu_instant: CpuInstant,
+    cpu_energy: HashMap<Domain, Vec<u64>>,
+    cpu_times: CpuTimes,
+    cgroup_time: u64,
+    delta_millisec: u64,
+    values: RefCell<HashMap<Metric, f32>>,
+}
+
+impl Sample<'_> {
+    /// Returns the value of the metric. Temperatures are in the unit selected for the sampler.
+    pub fn get(&self, metric: Metric) -> f32 {
+        if let Some(value) = self.values.borrow().get(&metric) {
+            return *value;
+        }
+
+        let cpu = &self.sampler.cpu;
+        let gpu = &self.sampler.gpu;
+        let psu = &self.sampler.psu;
+        let fahrenheit = self.sampler.fahrenheit;
+        let value = match metric {
+            Metric::CpuTemperature => cpu.get_temp(fahrenheit),
+            Metric::CpuUsage => cpu.get_usage(self.cpu_instant) as f32,
+            Metric::CpuPower | Metric::CpuCorePower | Metric::DramPower | Metric::SystemPower => {
+                let domain = metric.domain().unwrap();
+                let initial_energy = self.cpu_energy.get(&domain).map_or(&[][..], |energy| energy.as_slice());
+                cpu.get_power(domain, initial_energy, self.delta_millisec) as f32
+            }
+            Metric::CpuFrequency => cpu.get_frequency(&self.cpu_times) as f32,
+            Metric::CpuFan => self.sampler.fan.get_speed() as f32,
+            Metric::GpuTemperature => gpu.get_temp(fahrenheit),
+            Metric::GpuUsage => gpu.get_usage() as f32,
+            Metric::GpuPower => gpu.get_power() as f32,
+            Metric::GpuFrequency => gpu.get_frequency() as f32,
+            Metric::PsuTemperature => psu.get_temp(fahrenheit),
+            Metric::PsuPower => psu.get_power() as f32,
+            Metric::PsuInputPower => psu.get_input_power() as f32,
+            Metric::PsuUsage => psu.get_usage() as f32,
+            Metric::PsuFan => psu.get_fan_speed() as f32,
+            Metric::CoolantTemperature => self.sampler.coolant.get_temp(fahrenheit),
+            Metric::RamUsage => memory::get_ram_usage() as f32,
+            Metric::RamUsed => memory::get_ram_used(),
+            Metric::SwapUsage => memory::get_swap_usage() as f32,
+            Metric::DiskTemperature => self.sampler.disk.get_temp(fahrenheit),
+            Metric::BoardTemperature => self.sampler.board.get_temp(fahrenheit),
+            Metric::Hwmon(ref symbol) => self.sampler.hwmon.get_value(symbol, fahrenheit),
+            Metric::External => self.sampler.external.get_value(fahrenheit),
+            Metric::CpuMaxCoreUsage => cpu.get_max_core_usage(&self.cpu_times) as f32,
+            Metric::CpuPerformanceUsage => cpu.get_cluster_usage(true, &self.cpu_times) as f32,
+            Metric::CpuEfficiencyUsage => cpu.get_cluster_usage(false, &self.cpu_times) as f32,
+            Metric::CpuActiveUsage => cpu.get_active_usage(&self.cpu_times) as f32,
+            Metric::CgroupUsage => cpu.get_cgroup_usage(self.cgroup_time, self.delta_millisec) as f32,
+        };
+        let value = match self.sampler.filters.borrow_mut().get_mut(&metric) {
+            Some(filter) => filter.apply(value, Duration::from_millis(self.delta_millisec)),
+            None => value,
+        };
+        self.values.borrow_mut().insert(metric, value);
+
+        value
+    }
+}
@@ -0,0 +1,156 @@
+//! Reads the time spent by the CPU and its cores from `/proc/stat`.
+
+use crate::error;
+use std::{fs::read_to_string, process::exit};
+
+/// Time spent by the CPU or a single core in clock ticks.
+#[derive(Clone, Copy, Default)]
+pub struct Time {
+    /// Time spent running tasks (user, nice, system, irq, softirq)
+    active: u64,
+    /// Time stolen by the hypervisor
+    steal: u64,
+    total: u64,
+}
+
+impl Time {
+    /// Calculates the utilization since the initial reading as a `0-1` number.
+    ///
+    /// The time waiting for I/O counts as idle, the stolen time counts as busy.
+    pub fn usage_since(&self, initial: &Time) -> f32 {
+        ratio(self.active + self.steal, initial.active + initial.steal, self.total, initial.total)
+    }
+
+    /// Calculates the utilization since the initial reading as a `0-1` number, excluding the I/O wait and stolen time.
+    pub fn active_usage_since(&self, initial: &Time) -> f32 {
+        ratio(self.active, initial.active, self.total, initial.total)
+    }
+}
+
+/// Times of the whole CPU and each online core.
+#[derive(Clone, Default)]
+pub struct CpuTimes {
+    pub total: Time,
+    pub cores: Vec<(usize, Time)>,
+}
+
+impl CpuTimes {
+    pub fn read() -> Self {
+        let stat = read_to_string("/proc/stat").unwrap_or_else(|_| {
+            error!("Failed to get CPU usage");
+            exit(1);
+        });
+
+        CpuTimes::parse(&stat)
+    }
+
+    /// Parses the `cpu` lines of `/proc/stat`.
+    fn parse(stat: &str) -> Self {
+        let mut times = CpuTimes::default();
+        for line in stat.lines().filter(|line| line.starts_with("cpu")) {
+            let mut fields = line.split_whitespace();
+            let name = fields.next().unwrap_or_default();
+            // user, nice, system, idle, iowait, irq, softirq, steal (guest time is part of user time)
+            let values: Vec<u64> = fields.take(8).map(|value| value.parse::<u64>().unwrap_or(0)).collect();
+            let value = |index: usize| values.get(index).copied().unwrap_or(0);
+            let time = Time {
+                active: value(0) + value(1) + value(2) + value(5) + value(6),
+                steal: value(7),
+                total: values.iter().sum(),
+            };
+            match name.strip_prefix("cpu").unwrap_or_default() {
+                "" => times.total = time,
+                id => {
+                    if let Ok(id) = id.parse::<usize>() {
+                        times.cores.push((id, time));
+                    }
+                }
+            }
+        }
+
+        times
+    }
+
+    /// Calculates the utilization of each core since the initial reading as `(core, 0-1)`.
+    pub fn core_usage_since(&self, initial: &CpuTimes) -> Vec<(usize, f32)> {
+        self.cores
+            .iter()
+            .filter_map(|(id, time)| {
+                let (_, initial_time) = initial.cores.iter().find(|(initial_id, _)| initial_id == id)?;
+                Some((*id, time.usage_since(initial_time)))
+            })
+            .collect()
+    }
+}
+
+/// Parses a list of CPUs formatted as `0-7,16,18`.
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim_end()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => Some((start.parse::<usize>().ok()?..=end.parse::<usize>().ok()?).collect()),
+            None => Some(vec![range.parse::<usize>().ok()?]),
+        })
+        .flatten()
+        .collect()
+}
+
+fn ratio(busy: u64, initial_busy: u64, total: u64, initial_total: u64) -> f32 {
+    let total = total.saturating_sub(initial_total);
+    if total == 0 {
+        return 0.0;
+    }
+
+    busy.saturating_sub(initial_busy) as f32 / total as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "\
+cpu  100 10 50 800 20 5 5 10 30 0
+cpu0 60 5 25 400 10 3 2 5 30 0
+cpu2 40 5 25 400 10 2 3 5 0 0
+intr 12345 0 0
+ctxt 67890
+";
+
+    #[test]
+    fn parses_total_and_core_times() {
+        let times = CpuTimes::parse(STAT);
+        assert_eq!((times.total.active, times.total.steal, times.total.total), (170, 10, 1000));
+
+        let ids: Vec<usize> = times.cores.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, [0, 2]);
+        let (_, core) = times.cores[0];
+        // Guest time is not added to the total, it is part of the user time
+        assert_eq!((core.active, core.steal, core.total), (95, 5, 510));
+    }
+
+    #[test]
+    fn missing_fields_are_zero() {
+        let times = CpuTimes::parse("cpu 10 0 10 80\ncpu0 10 0 x 80\n");
+        assert_eq!((times.total.active, times.total.steal, times.total.total), (20, 0, 100));
+        let (_, core) = times.cores[0];
+        assert_eq!((core.active, core.total), (10, 90));
+    }
+
+    #[test]
+    fn usage_since_initial_reading() {
+        let initial = CpuTimes::parse("cpu 100 0 0 100 0 0 0 0\ncpu0 100 0 0 100 0 0 0 0\n");
+        let current = CpuTimes::parse("cpu 150 0 0 130 10 0 0 10\ncpu0 200 0 0 100 0 0 0 0\n");
+        // 60 of 100 ticks were busy, including the stolen time
+        assert_eq!(current.total.usage_since(&initial.total), 0.6);
+        assert_eq!(current.total.active_usage_since(&initial.total), 0.5);
+        assert_eq!(current.core_usage_since(&initial), [(0, 1.0)]);
+        assert_eq!(initial.total.usage_since(&initial.total), 0.0);
+    }
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("5"), [5]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! Reads the temperature of the disks and the motherboard from their hwmon sensors.
+//!
+//! The sensors are discovered automatically, or they can be selected as `chip:label` in the configuration file:
+//! ```ini
+//! [disk_temp]
+//! sensor = nvme:Sensor 2
+//!
+//! [board_temp]
+//! sensor = nct6798:AUXTIN1
+//! ```
+
+use super::hwmon::{self, Calibration};
+use crate::{error, utils::config::Config, warning};
+use std::{fs::read_to_string, process::exit};
+
+/// Temperature inputs of the disks as `(chip, label)`, all of them are used.
+const DISK_SENSORS: [(&str, &str); 2] = [
+    // NVMe SSDs
+    ("nvme", "Composite"),
+    // SATA disks
+    ("drivetemp", "temp1"),
+];
+
+/// Candidates of the motherboard temperature sensor in the order of priority as `(chip, label)`.
+const BOARD_SENSORS: [(&str, &str); 5] = [
+    ("nct6*", "SYSTIN"),
+    ("nct6*", "AUXTIN0"),
+    ("w83627ehf", "System Temp"),
+    ("asusec", "Motherboard"),
+    ("asus_wmi_sensors", "Motherboard Temperature"),
+];
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Disk,
+    Board,
+}
+
+impl Kind {
+    const fn section(&self) -> &'static str {
+        match self {
+            Kind::Disk => "disk_temp",
+            Kind::Board => "board_temp",
+        }
+    }
+
+    const fn description(&self) -> &'static str {
+        match self {
+            Kind::Disk => "Disk",
+            Kind::Board => "Motherboard",
+        }
+    }
+}
+
+pub struct SystemTemp {
+    kind: Kind,
+    inputs: Vec<(String, Calibration)>,
+}
+
+impl SystemTemp {
+    /// Looks for the temperature sensors of all NVMe and SATA disks.
+    pub fn disk(config: &Config) -> Self {
+        Self::new(Kind::Disk, config)
+    }
+
+    /// Looks for the temperature sensor of the motherboard.
+    pub fn board(config: &Config) -> Self {
+        Self::new(Kind::Board, config)
+    }
+
+    fn new(kind: Kind, config: &Config) -> Self {
+        let chips = hwmon::chips();
+        let selection = config.sections(kind.section()).last().map(|section| {
+            section.get("sensor").unwrap_or_else(|| section.missing_error("sensor"))
+        });
+
+        let inputs = match (selection, kind) {
+            (Some(selection), _) => {
+                let (chip, channel) = selection.split_once(':').unwrap_or((selection, "temp1"));
+                let inputs = hwmon::find_temp_inputs(&chips, chip, channel);
+                if inputs.is_empty() {
+                    error!(format!("{} temperature sensor \"{selection}\" was not found", kind.description()));
+                    eprintln!("       Available sensors can be listed with --sensors.");
+                    exit(1);
+                }
+                inputs
+            }
+            (None, Kind::Disk) => DISK_SENSORS
+                .iter()
+                .flat_map(|(chip, channel)| hwmon::find_temp_inputs(&chips, chip, channel))
+                .collect(),
+            (None, Kind::Board) => BOARD_SENSORS
+                .iter()
+                .map(|(chip, channel)| hwmon::find_temp_inputs(&chips, chip, channel))
+                .find(|inputs| !inputs.is_empty())
+                .map(|inputs| inputs.into_iter().take(1).collect())
+                .unwrap_or_default(),
+        };
+
+        SystemTemp {
+            kind,
+            inputs: inputs
+                .iter()
+                .filter_map(|(name, input)| {
+                    let calibration = Calibration::read(config, name).unwrap_or(Calibration::NONE);
+                    Some((input.path.to_str()?.to_owned(), calibration))
+                })
+                .collect(),
+        }
+    }
+
+    /// Displays a warning message if no sensor is found.
+    pub fn warn_missing(&self) {
+        if self.inputs.is_empty() {
+            warning!(format!("No {} temperature sensor was found", self.kind.description().to_lowercase()));
+            eprintln!("         {} temperature will not be displayed.", self.kind.description());
+            eprintln!("         The sensor can be selected in the [{}] section of the configuration.", self.kind.section());
+        }
+    }
+
+    /// Reads the value of the temperature sensors and calculates it to be `˚C` or `˚F`.
+    ///
+    /// If multiple sensors are found (e.g. disks), the highest temperature is returned.
+    pub fn get_temp(&self, fahrenheit: bool) -> f32 {
+        // Disks in standby may fail to report their temperature
+        let temp = self
+            .inputs
+            .iter()
+            .filter_map(|(input, calibration)| {
+                let data = read_to_string(input).ok()?;
+                Some(calibration.apply(data.trim_end().parse::<i32>().ok()? as f32 / 1000.0))
+            })
+            .fold(f32::MIN, f32::max);
+        if temp == f32::MIN {
+            return 0.0;
+        }
+
+        if fahrenheit { temp * 9.0 / 5.0 + 32.0 } else { temp }
+    }
+}
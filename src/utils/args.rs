@@ -1,18 +1,20 @@
-use crate::{devices::Mode, error, monitor::gpu::pci::{get_gpu_list, Vendor}, CH510_PRODUCT_ID, CH510_VENDOR_ID, DEFAULT_VENDOR_ID};
+use crate::{devices::Mode, error, monitor::{gpu::pci::{get_gpu_list, Vendor}, hwmon}, CH510_PRODUCT_ID, CH510_VENDOR_ID, DEFAULT_VENDOR_ID};
 use colored::*;
 use hidapi::HidApi;
-use std::{collections::HashMap, env::args, process::exit, time::Duration};
+use std::{collections::HashMap, env::args, fs::read_to_string, process::exit, time::Duration};
 
 pub struct Args {
     pub mode: Mode,
     pub secondary: Mode,
     pub pid: u16,
     pub gpuid: Option<(Vendor, u8)>,
+    pub cpu_sensor: Option<String>,
     pub update: Duration,
     pub fahrenheit: bool,
     pub alarm: bool,
     pub rotate: u16,
     pub lead_zeros: bool,
+    pub config: Option<String>,
 }
 
 impl Args {
@@ -22,11 +24,13 @@ impl Args {
         let mut secondary = Mode::Default;
         let mut pid = 0;
         let mut gpuid = None;
+        let mut cpu_sensor = None;
         let mut update = Duration::from_millis(1000);
         let mut fahrenheit = false;
         let mut alarm = false;
         let mut rotate = 0;
         let mut lead_zeros = false;
+        let mut config = None;
 
         let mut i = 1;
         while i < args.len() {
@@ -103,6 +107,19 @@ impl Args {
                         exit(1);
                     }
                 }
+                "--cpu-sensor" => {
+                    if i + 1 < args.len() {
+                        if args[i + 1].is_empty() || args[i + 1].starts_with(':') {
+                            error!("Invalid CPU sensor");
+                            exit(1);
+                        }
+                        cpu_sensor = Some(args[i + 1].clone());
+                        i += 1;
+                    } else {
+                        error!("--cpu-sensor requires a value");
+                        exit(1);
+                    }
+                }
                 "-u" | "--update" => {
                     if i + 1 < args.len() {
                         match args[i + 1].parse::<u64>() {
@@ -156,6 +173,15 @@ impl Args {
                 "-z" | "--zeros" => {
                     lead_zeros = true;
                 }
+                "-c" | "--config" => {
+                    if i + 1 < args.len() {
+                        config = Some(args[i + 1].clone());
+                        i += 1;
+                    } else {
+                        error!("--config requires a value");
+                        exit(1);
+                    }
+                }
                 "-l" | "--list" => {
                     println!("Device list [{} | {}]", "PID".bright_green().bold(), "Name".bright_green());
                     println!("-----");
@@ -206,6 +232,30 @@ impl Args {
                     }
                     exit(0);
                 }
+                "--sensors" => {
+                    println!("Sensor list [{} | {}]", "CHIP:LABEL".bright_green().bold(), "Temperature".bright_green());
+                    println!("-----");
+                    let mut sensors = Vec::new();
+                    for chip in hwmon::chips() {
+                        for input in hwmon::inputs(&chip.path, "temp") {
+                            sensors.push((format!("{}:{}", chip.name, input.name("temp")), input.path));
+                        }
+                    }
+                    for zone in hwmon::thermal_zones() {
+                        sensors.push((format!("thermal:{}", zone.name("thermal_zone")), zone.path));
+                    }
+                    for (name, path) in &sensors {
+                        let temp = read_to_string(path)
+                            .ok()
+                            .and_then(|data| data.trim_end().parse::<i32>().ok())
+                            .map_or("N/A".to_owned(), |temp| format!("{:.1}°C", temp as f32 / 1000.0));
+                        println!("{} | {}", name.bright_green().bold(), temp.bright_green());
+                    }
+                    if sensors.is_empty() {
+                        println!("{}", "No temperature sensors were found".bright_black().italic())
+                    }
+                    exit(0);
+                }
                 "-h" | "--help" => {
                     println!("{} [OPTIONS]", "Usage: deepcool-digital-linux".bold());
                     println!("\n{}", "Options:".bold());
@@ -213,14 +263,17 @@ impl Args {
                     println!("  {}, {} <MODE>  Change the secondary display mode of your device (if supported)", "-s".bold(), "--secondary".bold());
                     println!("      {} <ID>          Specify the Product ID if multiple devices are connected", "--pid".bold());
                     println!("      {} <VENDOR:ID> Specify the nth GPU of a specific vendor to monitor (use ID 0 for integrated GPU)", "--gpuid".bold());
+                    println!("      {} <CHIP:LABEL> Specify the CPU temperature sensor (use * at the end of the label for the highest of all matches)", "--cpu-sensor".bold());
                     println!("\n  {}, {} <MILLISEC> Change the update interval of the display [default: 1000]", "-u".bold(), "--update".bold());
                     println!("  {}, {}        Change the temperature unit to °F", "-f".bold(), "--fahrenheit".bold());
                     println!("  {}, {}             Enable the alarm", "-a".bold(), "--alarm".bold());
                     println!("  {}, {} <DEGREE>   Rotate the display (LP Series only)", "-r".bold(), "--rotate".bold());
                     println!("  {}, {}             Display leading zeros (LD Series only)", "-z".bold(), "--zeros".bold());
+                    println!("  {}, {} <FILE>     Read additional settings from a configuration file", "-c".bold(), "--config".bold());
                     println!("\n{}", "Commands:".bold());
                     println!("  {}, {}         Print Product ID of the connected devices", "-l".bold(), "--list".bold());
                     println!("  {}, {}      Print all available GPUs", "-g".bold(), "--gpulist".bold());
+                    println!("      {}      Print all available temperature sensors", "--sensors".bold());
                     println!("  {}, {}         Print help", "-h".bold(), "--help".bold());
                     println!("  {}, {}      Print version", "-v".bold(), "--version".bold());
                     exit(0);
@@ -306,6 +359,15 @@ impl Args {
                                     exit(1);
                                 }
                             }
+                            'c' => {
+                                if i + 1 < args.len() && args[i].ends_with('c') {
+                                    config = Some(args[i + 1].clone());
+                                    i += 1;
+                                } else {
+                                    error!("--config requires a value");
+                                    exit(1);
+                                }
+                            }
                             'f' => fahrenheit = true,
                             'a' => alarm = true,
                             'z' => lead_zeros = true,
@@ -333,11 +395,13 @@ impl Args {
             secondary,
             pid,
             gpuid,
+            cpu_sensor,
             update,
             fahrenheit,
             alarm,
             rotate,
             lead_zeros,
+            config,
         }
     }
 }
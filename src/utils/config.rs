@@ -0,0 +1,95 @@
+//! Reads the optional configuration file.
+//!
+//! The file uses a simple INI format:
+//! ```ini
+//! # Comment
+//! [section]
+//! key = value
+//! ```
+//! Sections and keys can be repeated, they are kept in the order of appearance.
+
+use crate::error;
+use std::{fs::read_to_string, process::exit, str::FromStr};
+
+pub struct Section {
+    pub name: String,
+    entries: Vec<(String, String)>,
+}
+
+impl Section {
+    /// Returns the first value of the key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the keys of the section in the order of appearance.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| k.as_str())
+    }
+
+    /// Parses the first value of the key, exits the program if the value is invalid.
+    pub fn parse<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key).map(|value| {
+            value.parse::<T>().unwrap_or_else(|_| self.value_error(key, value))
+        })
+    }
+
+    /// Displays an error message about an invalid value and exits the program.
+    pub fn value_error<T>(&self, key: &str, value: &str) -> T {
+        error!(format!("Invalid value \"{value}\" for \"{key}\" in [{}]", self.name));
+        exit(1);
+    }
+
+    /// Displays an error message about a missing key and exits the program.
+    pub fn missing_error<T>(&self, key: &str) -> T {
+        error!(format!("Missing \"{key}\" in [{}]", self.name));
+        exit(1);
+    }
+}
+
+#[derive(Default)]
+pub struct Config {
+    sections: Vec<Section>,
+}
+
+impl Config {
+    /// Reads the configuration file, or returns an empty configuration if no path is specified.
+    pub fn read(path: Option<&str>) -> Self {
+        let path = match path {
+            Some(path) => path,
+            None => return Config::default(),
+        };
+        let data = read_to_string(path).unwrap_or_else(|_| {
+            error!(format!("Failed to read the configuration file \"{path}\""));
+            exit(1);
+        });
+
+        let mut sections: Vec<Section> = Vec::new();
+        for (line_id, line) in data.lines().enumerate() {
+            let line = line.trim();
+            // Skip comments and empty lines
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                sections.push(Section {
+                    name: name.trim().to_owned(),
+                    entries: Vec::new(),
+                });
+            } else if let (Some((key, value)), Some(section)) = (line.split_once('='), sections.last_mut()) {
+                section.entries.push((key.trim().to_owned(), value.trim().to_owned()));
+            } else {
+                error!(format!("Invalid configuration at line {}: {line}", line_id + 1));
+                exit(1);
+            }
+        }
+
+        Config { sections }
+    }
+
+    /// Returns all sections with the specified name in the order of appearance.
+    pub fn sections<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Section> {
+        self.sections.iter().filter(move |s| s.name == name)
+    }
+}
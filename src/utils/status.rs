@@ -1,4 +1,4 @@
-use crate::Mode;
+use crate::{devices::alarm::{AlarmEffect, AlarmSource}, Mode};
 use colored::*;
 use std::time::Duration;
 
@@ -20,12 +20,15 @@ pub enum AlarmState {
     Auto,
     On,
     Off,
-    NotSupported,
 }
 
 pub struct Alarm {
     pub state: AlarmState,
-    pub temp_limit: u8,
+    /// Symbol of the alarm source, or the metric of the temperature field for hardware alarms
+    pub source: String,
+    pub limit: u16,
+    pub hysteresis: u16,
+    pub effect: Option<AlarmEffect>,
     pub temp_warning: u8,
 }
 
@@ -57,30 +60,54 @@ pub fn print_device_status(
         }
     }
     println!("TEMP. UNIT: {}", temp_unit.symbol().bright_cyan());
+    let unit = if alarm.source == AlarmSource::CpuPower.symbol() { "W" } else { temp_unit.symbol() };
     match alarm.state {
         AlarmState::Auto => {
+            let mut settings = Vec::new();
+            if alarm.source != AlarmSource::CpuTemperature.symbol() {
+                settings.push(format!("source: {}", alarm.source.bright_cyan()));
+            }
             if alarm.temp_warning > 0 {
+                settings.push(format!("warning: {}", (alarm.temp_warning.to_string() + unit).bright_cyan()));
+            }
+            if settings.is_empty() {
                 println!(
-                    "ALARM:      {} | {} [warning: {}]",
+                    "ALARM:      {} | {}",
                     "auto".bright_green(),
-                    (alarm.temp_limit.to_string() + temp_unit.symbol()).bright_cyan(),
-                    (alarm.temp_warning.to_string() + temp_unit.symbol()).bright_cyan()
+                    (alarm.limit.to_string() + unit).bright_cyan()
                 );
             } else {
                 println!(
-                    "ALARM:      {} | {}",
+                    "ALARM:      {} | {} [{}]",
                     "auto".bright_green(),
-                    (alarm.temp_limit.to_string() + temp_unit.symbol()).bright_cyan()
+                    (alarm.limit.to_string() + unit).bright_cyan(),
+                    settings.join(", ")
+                );
+            }
+        }
+        AlarmState::On => {
+            let mut settings = Vec::new();
+            if alarm.source != AlarmSource::CpuTemperature.symbol() {
+                settings.push(format!("source: {}", alarm.source.bright_cyan()));
+            }
+            if alarm.hysteresis > 0 {
+                settings.push(format!("hysteresis: {}", (alarm.hysteresis.to_string() + unit).bright_cyan()));
+            }
+            if let Some(effect) = alarm.effect {
+                settings.push(format!("effect: {}", effect.symbol().bright_cyan()));
+            }
+            if settings.is_empty() {
+                println!("ALARM:      {} | {}", "on".bright_green(), (alarm.limit.to_string() + unit).bright_cyan());
+            } else {
+                println!(
+                    "ALARM:      {} | {} [{}]",
+                    "on".bright_green(),
+                    (alarm.limit.to_string() + unit).bright_cyan(),
+                    settings.join(", ")
                 );
             }
         }
-        AlarmState::On => println!(
-            "ALARM:      {} | {}",
-            "on".bright_green(),
-            (alarm.temp_limit.to_string() + temp_unit.symbol()).bright_cyan()
-        ),
         AlarmState::Off => println!("ALARM:      {}", "off".bright_red()),
-        AlarmState::NotSupported => println!("ALARM:      {}", "not supported".bright_black().italic()),
     }
     println!("-----");
     println!("Update interval: {}", format!("{:?}", update).bright_cyan());